@@ -9,12 +9,34 @@ fn is_unix(target: &str) -> bool {
     target.contains("solaris")
 }
 
+fn has_family(family: &str) -> bool {
+    std::env::var("CARGO_CFG_TARGET_FAMILY").map(|value| value.split(',').any(|value| value == family)).unwrap_or(false)
+}
+
 fn main() {
     use std::env;
 
     let target = env::var("TARGET").unwrap();
+    let os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+
+    let backend = if env::var_os("CARGO_FEATURE_MOCK").is_some() {
+        "mock"
+    } else if env::var_os("CARGO_FEATURE_CUSTOM_BACKEND").is_some() {
+        "custom"
+    } else if has_family("windows") {
+        "win32"
+    } else if os == "macos" || os == "ios" {
+        "apple"
+    } else if has_family("unix") {
+        "posix"
+    } else {
+        "custom"
+    };
+
+    println!("cargo:rustc-check-cfg=cfg(os_timer_backend, values(\"posix\", \"win32\", \"apple\", \"mock\", \"custom\"))");
+    println!("cargo:rustc-cfg=os_timer_backend=\"{}\"", backend);
 
-    if is_unix(&target) {
+    if backend == "posix" && is_unix(&target) {
         cc::Build::new().file("src/timer/posix.c").compile("libos-timer-posix-c.a");
     }
 }
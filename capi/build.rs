@@ -0,0 +1,26 @@
+#[cfg(feature = "header")]
+fn main() {
+    use std::env;
+    use std::path::Path;
+
+    let dir = env::var("CARGO_MANIFEST_DIR").expect("To have manifest dir");
+    let dir = Path::new(&dir);
+    let src = dir.join("../src/capi.rs");
+    let config = dir.join("cbindgen.toml");
+
+    println!("cargo:rerun-if-changed={}", src.display());
+    println!("cargo:rerun-if-changed={}", config.display());
+
+    let config = cbindgen::Config::from_file(&config).expect("To read cbindgen.toml");
+    cbindgen::Builder::new().with_config(config)
+                            .with_src(&src)
+                            .generate()
+                            .expect("To generate C header")
+                            .write_to_file(dir.join("include/os_timer.h"));
+}
+
+#[cfg(not(feature = "header"))]
+fn main() {
+    //Header is only regenerated on request
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -0,0 +1,54 @@
+#ifndef OS_TIMER_H
+#define OS_TIMER_H
+
+/* Generated by cbindgen from `src/capi.rs`, do not edit. */
+
+#include <stdbool.h>
+#include <stdint.h>
+
+/* Opaque timer. */
+typedef struct os_timer_t os_timer_t;
+
+//C callback, invoked with user's data on timer's thread.
+typedef void (*os_timer_cb)(void*);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+//Creates timer, invoking `cb` with `data` on expiration.
+//
+//Returns null on failure.
+os_timer_t *os_timer_new(os_timer_cb cb, void *data);
+
+//Schedules timer to alarm once after `timeout_ns` nanoseconds.
+bool os_timer_schedule_once(const os_timer_t *timer, uint64_t timeout_ns);
+
+//Schedules timer to alarm after `timeout_ns` and then every `interval_ns` nanoseconds.
+//
+//Zero `timeout_ns` is replaced with `interval_ns`. Returns `false` if both are zero.
+bool os_timer_schedule_interval(const os_timer_t *timer, uint64_t timeout_ns, uint64_t interval_ns);
+
+//Returns whether timer is scheduled.
+bool os_timer_is_scheduled(const os_timer_t *timer);
+
+//Writes nanoseconds remaining until next alarm into `remaining_ns`.
+//
+//Returns `false` if timer is not scheduled.
+bool os_timer_remaining(const os_timer_t *timer, uint64_t *remaining_ns);
+
+//Cancels timer, waiting for running callback to finish.
+//
+//Can be called from within timer's callback, in which case it doesn't wait.
+void os_timer_cancel(const os_timer_t *timer);
+
+//Destroys timer, waiting for running callback to finish.
+//
+//Can be called from within timer's callback, in which case timer is deleted once callback returns.
+void os_timer_destroy(os_timer_t *timer);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* OS_TIMER_H */
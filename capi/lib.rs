@@ -0,0 +1,5 @@
+//! Shared and static libraries of os-timer C API
+//!
+//! Header is located at `include/os_timer.h` and is generated from `src/capi.rs` by build script, when `header` feature is enabled.
+
+pub use os_timer::capi::*;
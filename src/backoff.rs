@@ -0,0 +1,261 @@
+use core::{cmp, time};
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use crate::Timer;
+use crate::sync::Lock;
+use crate::rand;
+
+extern crate alloc;
+use alloc::sync::Arc;
+
+//Timer cannot be scheduled with zero timeout
+const MIN_DELAY: time::Duration = time::Duration::from_nanos(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///Jitter applied to exponential delay.
+pub enum Jitter {
+    ///Exact exponential delay.
+    None,
+    ///Delay is random within `[0, delay]`
+    Full,
+    ///Delay is `delay / 2` plus random within `[0, delay / 2]`
+    Equal,
+    ///Delay is random within `[base, previous * factor]`, independent of attempt number.
+    Decorrelated,
+}
+
+#[derive(Debug, Clone, Copy)]
+///Exponential backoff policy.
+///
+///Delay before retry `n` (starting from zero) is `base * factor^n`, truncated by `cap` and then adjusted by `jitter`.
+pub struct Backoff {
+    base: time::Duration,
+    factor: f64,
+    cap: time::Duration,
+    jitter: Jitter,
+    max_attempts: u32,
+}
+
+impl Backoff {
+    #[inline(always)]
+    ///Creates new policy with `base` delay.
+    ///
+    ///Defaults:
+    ///
+    ///- `factor` is `2.0`
+    ///- `cap` is 60 seconds.
+    ///- No jitter.
+    ///- Unlimited number of attempts.
+    pub const fn new(base: time::Duration) -> Self {
+        Self {
+            base,
+            factor: 2.0,
+            cap: time::Duration::from_secs(60),
+            jitter: Jitter::None,
+            max_attempts: u32::max_value(),
+        }
+    }
+
+    #[inline(always)]
+    ///Sets multiplier, applied to delay after each attempt.
+    pub const fn factor(mut self, factor: f64) -> Self {
+        self.factor = factor;
+        self
+    }
+
+    #[inline(always)]
+    ///Sets maximum delay between attempts.
+    pub const fn cap(mut self, cap: time::Duration) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    #[inline(always)]
+    ///Sets jitter to apply on delay.
+    pub const fn jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    #[inline(always)]
+    ///Sets maximum number of attempts, including the first one.
+    ///
+    ///Zero is treated as one.
+    pub const fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    fn exponential(&self, retry: u32) -> time::Duration {
+        let mut delay = cmp::min(self.base, self.cap);
+
+        if self.factor > 1.0 {
+            for _ in 0..retry {
+                delay = match time::Duration::try_from_secs_f64(delay.as_secs_f64() * self.factor) {
+                    Ok(delay) if delay < self.cap => delay,
+                    _ => return self.cap,
+                };
+            }
+        }
+
+        delay
+    }
+
+    ///Calculates delay before retry `n`, updating `prev` delay.
+    fn delay(&self, retry: u32, prev: &mut time::Duration) -> time::Duration {
+        let delay = match self.jitter {
+            Jitter::None => self.exponential(retry),
+            Jitter::Full => rand::duration_up_to(self.exponential(retry)),
+            Jitter::Equal => {
+                let delay = self.exponential(retry);
+                let half = delay / 2;
+                half + rand::duration_up_to(delay - half)
+            },
+            Jitter::Decorrelated => {
+                let high = match time::Duration::try_from_secs_f64(prev.as_secs_f64() * self.factor) {
+                    Ok(high) => cmp::min(high, self.cap),
+                    Err(_) => self.cap,
+                };
+                cmp::min(rand::duration_between(self.base, high), self.cap)
+            },
+        };
+
+        *prev = delay;
+        cmp::max(delay, MIN_DELAY)
+    }
+
+    ///Starts running `op` until it succeeds or runs out of attempts.
+    ///
+    ///First attempt is performed immediately on calling thread, while retries are run on timer's thread.
+    ///
+    ///`on_complete` is invoked with outcome of last attempt.
+    ///
+    ///On failure to create timer, returns `None`
+    pub fn retry<T, E, F, C>(self, mut op: F, on_complete: C) -> Option<Retry>
+        where F: FnMut() -> Result<T, E> + Send + 'static,
+              C: FnOnce(Result<T, E>) + Send + 'static
+    {
+        let mut on_complete = Some(on_complete);
+        let run = move |is_last: bool| match op() {
+            Err(_) if !is_last => false,
+            result => {
+                if let Some(on_complete) = on_complete.take() {
+                    on_complete(result);
+                }
+                true
+            }
+        };
+
+        let state = Arc::new(RetryState {
+            timer: unsafe {
+                Timer::uninit()
+            },
+            policy: self,
+            attempts: AtomicU32::new(0),
+            finished: AtomicBool::new(false),
+            run: Lock::new((run, self.base)),
+        });
+
+        if !state.timer.init_owned(&state, RetryState::attempt) {
+            return None;
+        }
+
+        state.attempt();
+        Some(Retry {
+            state,
+        })
+    }
+}
+
+struct RetryState<F> {
+    timer: Timer,
+    policy: Backoff,
+    attempts: AtomicU32,
+    finished: AtomicBool,
+    //Attempt runner and previous delay
+    run: Lock<(F, time::Duration)>,
+}
+
+impl<F: FnMut(bool) -> bool> RetryState<F> {
+    fn attempt(&self) {
+        let mut run = self.run.lock();
+        if self.finished.load(Ordering::Acquire) {
+            return;
+        }
+
+        let attempt = self.attempts.fetch_add(1, Ordering::AcqRel) + 1;
+        if (run.0)(attempt >= self.policy.max_attempts) {
+            self.finished.store(true, Ordering::Release);
+        } else {
+            let delay = self.policy.delay(attempt - 1, &mut run.1);
+            self.timer.schedule_once(delay);
+        }
+    }
+}
+
+trait RetryControl: Send + Sync {
+    fn attempts(&self) -> u32;
+    fn is_finished(&self) -> bool;
+    fn cancel(&self);
+    fn close(&self);
+}
+
+impl<F: FnMut(bool) -> bool + Send> RetryControl for RetryState<F> {
+    #[inline(always)]
+    fn attempts(&self) -> u32 {
+        self.attempts.load(Ordering::Acquire)
+    }
+
+    #[inline(always)]
+    fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    fn cancel(&self) {
+        self.finished.store(true, Ordering::Release);
+        self.timer.cancel();
+    }
+
+    #[inline]
+    fn close(&self) {
+        self.finished.store(true, Ordering::Release);
+        self.timer.close();
+    }
+}
+
+///Running retry operation.
+///
+///Dropping it cancels pending retries, waiting for running attempt to finish.
+pub struct Retry {
+    state: Arc<dyn RetryControl>,
+}
+
+impl Retry {
+    #[inline]
+    ///Returns number of attempts made so far.
+    pub fn attempts(&self) -> u32 {
+        self.state.attempts()
+    }
+
+    #[inline]
+    ///Returns whether operation is complete or cancelled.
+    pub fn is_finished(&self) -> bool {
+        self.state.is_finished()
+    }
+
+    #[inline]
+    ///Cancels pending retries.
+    ///
+    ///Completion callback is not invoked, unless operation already finished.
+    pub fn cancel(&self) {
+        self.state.cancel()
+    }
+}
+
+impl Drop for Retry {
+    #[inline]
+    fn drop(&mut self) {
+        self.state.close()
+    }
+}
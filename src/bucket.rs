@@ -0,0 +1,128 @@
+use core::time;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::Timer;
+
+extern crate alloc;
+use alloc::sync::Arc;
+
+#[cfg(feature = "std")]
+use crate::sync::Lock;
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+
+struct BucketState {
+    timer: Timer,
+    tokens: AtomicUsize,
+    capacity: usize,
+    refill: usize,
+    //Threads blocked in `acquire`, woken up on each refill
+    #[cfg(feature = "std")]
+    waiters: Lock<Vec<std::thread::Thread>>,
+}
+
+impl BucketState {
+    fn on_refill(&self) {
+        let capacity = self.capacity;
+        let refill = self.refill;
+        let _ = self.tokens.fetch_update(Ordering::AcqRel, Ordering::Acquire, |tokens| match tokens < capacity {
+            true => Some(core::cmp::min(tokens.saturating_add(refill), capacity)),
+            false => None,
+        });
+
+        #[cfg(feature = "std")]
+        {
+            let waiters = core::mem::take(&mut *self.waiters.lock());
+            for waiter in waiters {
+                waiter.unpark();
+            }
+        }
+    }
+}
+
+///Token bucket rate limiter, refilled periodically by timer.
+///
+///Acquiring tokens is lock-free and doesn't read clock, as tokens are added by timer's callback.
+///Bucket starts full.
+pub struct TokenBucket {
+    state: Arc<BucketState>,
+}
+
+impl TokenBucket {
+    ///Creates new bucket holding up to `capacity` tokens, adding `refill` tokens every `interval`.
+    ///
+    ///On failure to create timer, returns `None`
+    pub fn new(capacity: usize, refill: usize, interval: time::Duration) -> Option<Self> {
+        assert!(refill > 0, "Cannot refill bucket with zero tokens");
+        assert!(interval != time::Duration::ZERO, "Cannot refill bucket with zero interval");
+
+        let state = Arc::new(BucketState {
+            timer: unsafe {
+                Timer::uninit()
+            },
+            tokens: AtomicUsize::new(capacity),
+            capacity,
+            refill,
+            #[cfg(feature = "std")]
+            waiters: Lock::new(Vec::new()),
+        });
+
+        if !state.timer.init_owned(&state, BucketState::on_refill) {
+            return None;
+        }
+
+        match state.timer.schedule_interval(interval, interval) {
+            true => Some(Self {
+                state,
+            }),
+            false => None,
+        }
+    }
+
+    ///Takes `n` tokens, if available.
+    ///
+    ///Returns `false` without taking anything, if there is not enough tokens.
+    pub fn try_acquire(&self, n: usize) -> bool {
+        self.state.tokens.fetch_update(Ordering::AcqRel, Ordering::Acquire, |tokens| tokens.checked_sub(n)).is_ok()
+    }
+
+    ///Takes `n` tokens, blocking until enough is available.
+    ///
+    ///Without `std` feature it spins, otherwise thread is parked until next refill.
+    pub fn acquire(&self, n: usize) {
+        assert!(n <= self.state.capacity, "Cannot acquire more tokens than bucket capacity");
+
+        while !self.try_acquire(n) {
+            #[cfg(feature = "std")]
+            {
+                self.state.waiters.lock().push(std::thread::current());
+                //Refill might happen before registration
+                if self.try_acquire(n) {
+                    break;
+                }
+                std::thread::park();
+            }
+            #[cfg(not(feature = "std"))]
+            core::hint::spin_loop();
+        }
+    }
+
+    #[inline]
+    ///Returns number of currently available tokens.
+    pub fn available(&self) -> usize {
+        self.state.tokens.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    ///Returns maximum number of tokens.
+    pub fn capacity(&self) -> usize {
+        self.state.capacity
+    }
+}
+
+impl Drop for TokenBucket {
+    #[inline]
+    fn drop(&mut self) {
+        self.state.timer.close();
+    }
+}
@@ -0,0 +1,104 @@
+//! C API
+//!
+//! Exported functions are declared in `capi/include/os_timer.h`, which is generated from this module.
+//! Shared and static libraries are built by `os-timer-capi` crate.
+
+use core::{ptr, time};
+use core::ffi::c_void;
+
+use crate::{Timer, Callback};
+
+extern crate alloc;
+use alloc::boxed::Box;
+
+///C callback, invoked with user's data on timer's thread.
+pub type os_timer_cb = Option<unsafe extern "C" fn(*mut c_void)>;
+
+#[no_mangle]
+///Creates timer, invoking `cb` with `data` on expiration.
+///
+///Returns null on failure.
+pub unsafe extern "C" fn os_timer_new(cb: os_timer_cb, data: *mut c_void) -> *mut Timer {
+    let cb = match cb {
+        Some(cb) => cb,
+        None => return ptr::null_mut(),
+    };
+
+    let data = data as usize;
+    match Timer::new(Callback::closure(move || cb(data as *mut c_void))) {
+        Some(timer) => Box::into_raw(Box::new(timer)),
+        None => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+///Schedules timer to alarm once after `timeout_ns` nanoseconds.
+pub unsafe extern "C" fn os_timer_schedule_once(timer: *const Timer, timeout_ns: u64) -> bool {
+    match timer.as_ref() {
+        Some(timer) if timeout_ns > 0 => timer.schedule_once(time::Duration::from_nanos(timeout_ns)),
+        _ => false,
+    }
+}
+
+#[no_mangle]
+///Schedules timer to alarm after `timeout_ns` and then every `interval_ns` nanoseconds.
+///
+///Zero `timeout_ns` is replaced with `interval_ns`. Returns `false` if both are zero.
+pub unsafe extern "C" fn os_timer_schedule_interval(timer: *const Timer, timeout_ns: u64, interval_ns: u64) -> bool {
+    match timer.as_ref() {
+        Some(timer) if timeout_ns > 0 || interval_ns > 0 => {
+            let timeout_ns = if timeout_ns == 0 { interval_ns } else { timeout_ns };
+            timer.schedule_interval(time::Duration::from_nanos(timeout_ns), time::Duration::from_nanos(interval_ns))
+        },
+        _ => false,
+    }
+}
+
+#[no_mangle]
+///Returns whether timer is scheduled.
+pub unsafe extern "C" fn os_timer_is_scheduled(timer: *const Timer) -> bool {
+    match timer.as_ref() {
+        Some(timer) => timer.is_scheduled(),
+        None => false,
+    }
+}
+
+#[no_mangle]
+///Writes nanoseconds remaining until next alarm into `remaining_ns`.
+///
+///Returns `false` if timer is not scheduled.
+pub unsafe extern "C" fn os_timer_remaining(timer: *const Timer, remaining_ns: *mut u64) -> bool {
+    let remaining = match timer.as_ref() {
+        Some(timer) => timer.remaining(),
+        None => None,
+    };
+
+    match (remaining, remaining_ns.is_null()) {
+        (Some(remaining), false) => {
+            *remaining_ns = remaining.as_nanos() as u64;
+            true
+        },
+        (Some(_), true) => true,
+        (None, _) => false,
+    }
+}
+
+#[no_mangle]
+///Cancels timer, waiting for running callback to finish.
+///
+///Can be called from within timer's callback, in which case it doesn't wait.
+pub unsafe extern "C" fn os_timer_cancel(timer: *const Timer) {
+    if let Some(timer) = timer.as_ref() {
+        timer.cancel();
+    }
+}
+
+#[no_mangle]
+///Destroys timer, waiting for running callback to finish.
+///
+///Can be called from within timer's callback, in which case timer is deleted once callback returns.
+pub unsafe extern "C" fn os_timer_destroy(timer: *mut Timer) {
+    if !timer.is_null() {
+        drop(Box::from_raw(timer));
+    }
+}
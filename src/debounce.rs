@@ -0,0 +1,238 @@
+use core::time;
+use core::cell::UnsafeCell;
+use core::sync::atomic::{self, AtomicBool, Ordering};
+
+use crate::Timer;
+use crate::sync::Slot;
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+
+struct DebounceState<T> {
+    timer: Timer,
+    value: Slot<T>,
+    quiet: time::Duration,
+}
+
+///Debouncer, emitting only the last pushed value once `quiet` period passes without new values.
+///
+///Callback is invoked from timer's thread.
+pub struct Debouncer<T> {
+    state: Arc<DebounceState<T>>,
+}
+
+impl<T: Send + 'static> Debouncer<T> {
+    ///Creates new debouncer, which invokes `cb` with last value after `quiet` period.
+    ///
+    ///On failure to create timer, returns `None`
+    pub fn new<F: FnMut(T) + Send + 'static>(quiet: time::Duration, mut cb: F) -> Option<Self> {
+        let state = Arc::new(DebounceState {
+            timer: unsafe {
+                Timer::uninit()
+            },
+            value: Slot::new(),
+            quiet,
+        });
+
+        let on_expire = move |state: &DebounceState<T>| {
+            if let Some(value) = state.value.take() {
+                (cb)(value);
+            }
+        };
+
+        match state.timer.init_owned(&state, on_expire) {
+            true => Some(Self {
+                state,
+            }),
+            false => None,
+        }
+    }
+
+    #[inline]
+    ///Stores `value`, replacing pending one, and restarts `quiet` period.
+    pub fn push(&self, value: T) {
+        self.state.value.put(value);
+        self.state.timer.schedule_once(self.state.quiet);
+    }
+
+    #[inline]
+    ///Returns whether there is value waiting to be emitted.
+    pub fn is_pending(&self) -> bool {
+        !self.state.value.is_empty()
+    }
+
+    #[inline]
+    ///Cancels timer, dropping pending value, if any.
+    pub fn cancel(&self) {
+        self.state.timer.cancel();
+        let _ = self.state.value.take();
+    }
+}
+
+impl<T> Drop for Debouncer<T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.state.timer.close();
+    }
+}
+
+///Invokes callback from any thread, but never concurrently.
+struct Emitter<T> {
+    //Set while callback is running
+    busy: AtomicBool,
+    //Value, emitted by running callback once it returns
+    deferred: Slot<T>,
+    cb: UnsafeCell<Box<dyn FnMut(T) + Send>>,
+}
+
+unsafe impl<T: Send> Sync for Emitter<T> {}
+
+impl<T> Emitter<T> {
+    ///Invokes callback with `value`, or defers it to the running callback without waiting for it.
+    fn emit(&self, value: T) {
+        self.deferred.put(value);
+
+        loop {
+            //Value could be deferred right after running callback has been finished, so check it again
+            atomic::fence(Ordering::SeqCst);
+            if self.deferred.is_empty() || self.busy.compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire).is_err() {
+                return;
+            }
+
+            while let Some(value) = self.deferred.take() {
+                //Only single thread holds `busy`, so callback is never aliased
+                unsafe {
+                    (*self.cb.get())(value);
+                }
+            }
+            self.busy.store(false, Ordering::Release);
+        }
+    }
+}
+
+struct ThrottleState<T> {
+    timer: Timer,
+    pending: Slot<T>,
+    //Whether window is currently open
+    active: AtomicBool,
+    window: time::Duration,
+    leading: AtomicBool,
+    trailing: AtomicBool,
+    emitter: Emitter<T>,
+}
+
+impl<T> ThrottleState<T> {
+    fn on_expire(&self) {
+        loop {
+            if let Some(value) = self.pending.take() {
+                //Trailing value starts new window
+                self.timer.schedule_once(self.window);
+                self.emitter.emit(value);
+                return;
+            }
+
+            self.active.store(false, Ordering::Release);
+
+            //Value could be pushed right before window has been closed
+            if self.pending.is_empty() || self.active.compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+///Throttler, emitting at most one value per `window`.
+///
+///By default both leading and trailing edges are enabled:
+///
+///- Leading edge emits first value immediately, opening new window.
+///- Trailing edge emits last value pushed during window, once it ends.
+///
+///Leading edge is emitted from thread calling `push`, while trailing edge from timer's thread.
+///Callback is never invoked concurrently: value, emitted while callback is running, is passed to it after it returns.
+pub struct Throttler<T> {
+    state: Arc<ThrottleState<T>>,
+}
+
+impl<T: Send + 'static> Throttler<T> {
+    ///Creates new throttler, which invokes `cb` at most once per `window`.
+    ///
+    ///On failure to create timer, returns `None`
+    pub fn new<F: FnMut(T) + Send + 'static>(window: time::Duration, cb: F) -> Option<Self> {
+        let state = Arc::new(ThrottleState {
+            timer: unsafe {
+                Timer::uninit()
+            },
+            pending: Slot::new(),
+            active: AtomicBool::new(false),
+            window,
+            leading: AtomicBool::new(true),
+            trailing: AtomicBool::new(true),
+            emitter: Emitter {
+                busy: AtomicBool::new(false),
+                deferred: Slot::new(),
+                cb: UnsafeCell::new(Box::new(cb)),
+            },
+        });
+
+        match state.timer.init_owned(&state, ThrottleState::on_expire) {
+            true => Some(Self {
+                state,
+            }),
+            false => None,
+        }
+    }
+
+    #[inline]
+    ///Sets whether to emit value at the start of window.
+    pub fn leading(self, leading: bool) -> Self {
+        self.state.leading.store(leading, Ordering::Release);
+        self
+    }
+
+    #[inline]
+    ///Sets whether to emit last value at the end of window.
+    pub fn trailing(self, trailing: bool) -> Self {
+        self.state.trailing.store(trailing, Ordering::Release);
+        self
+    }
+
+    ///Pushes `value`, emitting or storing it depending on window state.
+    pub fn push(&self, value: T) {
+        let state = &*self.state;
+        let trailing = state.trailing.load(Ordering::Acquire);
+
+        if state.active.compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire).is_ok() {
+            state.timer.schedule_once(state.window);
+            if state.leading.load(Ordering::Acquire) {
+                state.emitter.emit(value);
+            } else if trailing {
+                state.pending.put(value);
+            }
+        } else if trailing {
+            state.pending.put(value);
+        }
+    }
+
+    #[inline]
+    ///Returns whether window is currently open.
+    pub fn is_active(&self) -> bool {
+        self.state.active.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    ///Cancels timer, closing current window and dropping pending value, if any.
+    pub fn cancel(&self) {
+        self.state.timer.cancel();
+        let _ = self.state.pending.take();
+        self.state.active.store(false, Ordering::Release);
+    }
+}
+
+impl<T> Drop for Throttler<T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.state.timer.close();
+    }
+}
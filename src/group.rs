@@ -0,0 +1,94 @@
+use crate::{Timer, TimerHandle};
+use crate::sync::Lock;
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+///Group of timers, controlled together.
+///
+///Holds handles to the timers, so dropped timers are simply skipped.
+pub struct TimerGroup {
+    timers: Lock<Vec<TimerHandle>>,
+}
+
+impl TimerGroup {
+    #[inline]
+    ///Creates empty group.
+    pub const fn new() -> Self {
+        Self {
+            timers: Lock::new(Vec::new()),
+        }
+    }
+
+    #[inline]
+    ///Adds `timer` to the group.
+    pub fn add(&self, timer: &Timer) {
+        self.add_handle(timer.handle());
+    }
+
+    #[inline]
+    ///Adds timer, referenced by `handle`, to the group.
+    pub fn add_handle(&self, handle: TimerHandle) {
+        self.timers.lock().push(handle);
+    }
+
+    #[inline]
+    ///Returns number of timers in the group, including dropped ones.
+    pub fn len(&self) -> usize {
+        self.timers.lock().len()
+    }
+
+    #[inline]
+    ///Returns whether group is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///Cancels every timer of the group.
+    pub fn cancel_all(&self) {
+        for handle in self.timers.lock().iter() {
+            let _ = handle.cancel();
+        }
+    }
+
+    ///Pauses every scheduled timer of the group.
+    ///
+    ///Returns number of paused timers.
+    pub fn pause_all(&self) -> usize {
+        self.timers.lock().iter().filter(|handle| match handle.get_inner() {
+            Ok(state) => state.pause(),
+            Err(_) => false,
+        }).count()
+    }
+
+    ///Resumes every paused timer of the group.
+    ///
+    ///Returns number of resumed timers.
+    pub fn resume_all(&self) -> usize {
+        self.timers.lock().iter().filter(|handle| match handle.get_inner() {
+            Ok(state) => state.resume(),
+            Err(_) => false,
+        }).count()
+    }
+
+    ///Stops every timer of the group for good, waiting until none of their callbacks is running.
+    ///
+    ///Timers can no longer be scheduled afterwards.
+    pub fn shutdown(self) {
+        let timers = core::mem::take(&mut *self.timers.lock());
+        for handle in timers.iter() {
+            let _ = handle.cancel();
+        }
+        //Dropped timers might still run callbacks too
+        for handle in timers.iter() {
+            handle.state().close();
+        }
+    }
+}
+
+impl Default for TimerGroup {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -4,12 +4,41 @@
 //!
 //! - Posix timer requires compilation of C shim (i.e. Correct C compiler must be available when
 //! compiling for posix target).
+//!
+//! # Features
+//!
+//! - `std` - Enables APIs relying on `std`, such as `Schedule::until`.
+//! - `capi` - Exports C API, declared in `capi/include/os_timer.h`. Shared and static libraries are built by `capi` crate.
+//! - `mock` - Replaces OS timers with virtual clock, which is moved by `mock::advance`, firing expired timers synchronously.
+//! - `custom-backend` - Replaces OS timers with backend, installed by `backend::install`. Always used on targets without OS timers.
 
 #![no_std]
 #![warn(missing_docs)]
-#![cfg_attr(feature = "cargo-clippy", allow(clippy::style))]
+#![allow(clippy::style)]
+
+#[cfg(feature = "std")]
+extern crate std;
 
-#[cfg(any(windows, unix))]
 mod timer;
-#[cfg(any(windows, unix))]
 pub use timer::*;
+mod sync;
+mod debounce;
+pub use debounce::{Debouncer, Throttler};
+mod rand;
+mod backoff;
+pub use backoff::{Backoff, Jitter, Retry};
+mod wheel;
+pub use wheel::{TimerWheel, WheelId};
+mod queue;
+pub use queue::{TimerQueue, TimerId};
+mod bucket;
+pub use bucket::TokenBucket;
+mod spawn;
+pub use spawn::spawn_after;
+mod scope;
+pub use scope::{scope, Scope};
+mod group;
+pub use group::TimerGroup;
+#[cfg(feature = "capi")]
+#[allow(non_camel_case_types)]
+pub mod capi;
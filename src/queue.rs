@@ -0,0 +1,193 @@
+use core::{cmp, time};
+
+use crate::Timer;
+use crate::timer::monotonic_now;
+use crate::sync::Lock;
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use alloc::collections::{BinaryHeap, BTreeMap};
+
+//Timer cannot be scheduled with zero timeout
+const MIN_DELAY: time::Duration = time::Duration::from_nanos(1);
+
+type Job = Box<dyn FnOnce() + Send>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+///Handle to the timeout, scheduled on `TimerQueue`
+pub struct TimerId(u64);
+
+#[derive(PartialEq, Eq)]
+struct Deadline {
+    at: time::Duration,
+    id: TimerId,
+}
+
+impl PartialOrd for Deadline {
+    #[inline(always)]
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Deadline {
+    #[inline(always)]
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        //Reversed to turn max-heap into min-heap, ties resolved by insertion order.
+        other.at.cmp(&self.at).then_with(|| other.id.cmp(&self.id))
+    }
+}
+
+struct Queue {
+    next_id: u64,
+    deadlines: BinaryHeap<Deadline>,
+    jobs: BTreeMap<TimerId, Job>,
+    //Deadline to which timer is currently armed
+    armed: Option<time::Duration>,
+}
+
+impl Queue {
+    ///Removes deadlines of cancelled jobs from the top of heap.
+    fn prune(&mut self) {
+        while let Some(top) = self.deadlines.peek() {
+            if self.jobs.contains_key(&top.id) {
+                break;
+            }
+            self.deadlines.pop();
+        }
+
+        //Avoid unbounded growth when cancelling far away deadlines.
+        if self.deadlines.len() > 2 * self.jobs.len() + 64 {
+            let jobs = &self.jobs;
+            self.deadlines.retain(|deadline| jobs.contains_key(&deadline.id));
+        }
+    }
+
+    fn arm(&mut self, timer: &Timer, now: time::Duration) {
+        self.prune();
+        match self.deadlines.peek() {
+            Some(next) => if self.armed != Some(next.at) {
+                let timeout = cmp::max(next.at.saturating_sub(now), MIN_DELAY);
+                self.armed = Some(next.at);
+                timer.schedule_once(timeout);
+            },
+            None => self.armed = None,
+        }
+    }
+}
+
+struct QueueState {
+    timer: Timer,
+    queue: Lock<Queue>,
+}
+
+impl QueueState {
+    fn on_expire(&self) {
+        let mut expired = Vec::new();
+        {
+            let mut queue = self.queue.lock();
+            let now = monotonic_now();
+            queue.armed = None;
+
+            while let Some(top) = queue.deadlines.peek() {
+                if top.at > now {
+                    break;
+                }
+
+                let id = top.id;
+                queue.deadlines.pop();
+                if let Some(job) = queue.jobs.remove(&id) {
+                    expired.push(job);
+                }
+            }
+
+            queue.arm(&self.timer, now);
+        }
+
+        //Run outside of lock, so that jobs can schedule new timeouts.
+        for job in expired {
+            job();
+        }
+    }
+}
+
+///Timer queue, multiplexing timeouts with exact deadlines onto single OS timer.
+///
+///Deadlines are kept in min-heap and underlying timer is always armed to the earliest one.
+///Insertion and cancellation are `O(log n)`.
+///
+///Jobs are invoked on timer's thread.
+pub struct TimerQueue {
+    state: Arc<QueueState>,
+}
+
+impl TimerQueue {
+    ///Creates new queue.
+    ///
+    ///On failure to create timer, returns `None`
+    pub fn new() -> Option<Self> {
+        let state = Arc::new(QueueState {
+            timer: unsafe {
+                Timer::uninit()
+            },
+            queue: Lock::new(Queue {
+                next_id: 0,
+                deadlines: BinaryHeap::new(),
+                jobs: BTreeMap::new(),
+                armed: None,
+            }),
+        });
+
+        match state.timer.init_owned(&state, QueueState::on_expire) {
+            true => Some(Self {
+                state,
+            }),
+            false => None,
+        }
+    }
+
+    ///Schedules `job` to run once `timeout` passes.
+    pub fn insert<F: FnOnce() + Send + 'static>(&self, timeout: time::Duration, job: F) -> TimerId {
+        let mut queue = self.state.queue.lock();
+        let now = monotonic_now();
+
+        let id = TimerId(queue.next_id);
+        queue.next_id += 1;
+        queue.jobs.insert(id, Box::new(job));
+        queue.deadlines.push(Deadline {
+            at: now.saturating_add(timeout),
+            id,
+        });
+
+        queue.arm(&self.state.timer, now);
+        id
+    }
+
+    #[inline]
+    ///Cancels timeout, returning whether it was still pending.
+    pub fn cancel(&self, id: TimerId) -> bool {
+        let job = self.state.queue.lock().jobs.remove(&id);
+        job.is_some()
+    }
+
+    #[inline]
+    ///Returns number of pending timeouts.
+    pub fn len(&self) -> usize {
+        self.state.queue.lock().jobs.len()
+    }
+
+    #[inline]
+    ///Returns whether there are no pending timeouts.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Drop for TimerQueue {
+    #[inline]
+    fn drop(&mut self) {
+        self.state.timer.close();
+    }
+}
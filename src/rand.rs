@@ -0,0 +1,60 @@
+//! Internal pseudo random generator
+//!
+//! Not suitable for anything but spreading timeouts.
+
+use core::time;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::timer::{monotonic_now, thread_id};
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[inline(always)]
+const fn splitmix64(mut value: u64) -> u64 {
+    value = value.wrapping_add(0x9e3779b97f4a7c15);
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d049bb133111eb);
+    value ^ (value >> 31)
+}
+
+///Returns next pseudo random number.
+///
+///Uses global counter, mixed with address of stack variable, current time and thread, to differ between processes and threads.
+///
+///There is no source of entropy, so on targets without ASLR processes, started at the same time, may produce the same sequence.
+pub(crate) fn next_u64() -> u64 {
+    let local = 0u8;
+    let seed = &local as *const u8 as usize as u64;
+    let now = monotonic_now().as_nanos() as u64;
+    let thread = thread_id() as u64;
+    let counter = COUNTER.fetch_add(1, Ordering::Relaxed) as u64;
+
+    let seed = splitmix64(seed) ^ splitmix64(now) ^ splitmix64(thread.rotate_left(32));
+    splitmix64(seed ^ counter.wrapping_mul(0x9e3779b97f4a7c15))
+}
+
+///Returns uniformly distributed duration within `[0, max]`
+pub(crate) fn duration_up_to(max: time::Duration) -> time::Duration {
+    let max = max.as_nanos();
+    let max = if max > u64::max_value() as u128 {
+        u64::max_value()
+    } else {
+        max as u64
+    };
+
+    let value = match max.checked_add(1) {
+        Some(range) => next_u64() % range,
+        None => next_u64(),
+    };
+
+    time::Duration::from_nanos(value)
+}
+
+#[inline]
+///Returns uniformly distributed duration within `[low, high]`
+pub(crate) fn duration_between(low: time::Duration, high: time::Duration) -> time::Duration {
+    match high.checked_sub(low) {
+        Some(range) => low + duration_up_to(range),
+        None => low,
+    }
+}
@@ -0,0 +1,72 @@
+use core::marker::PhantomData;
+
+use crate::{Timer, Callback};
+use crate::sync::Lock;
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+//Timers are boxed, so that references to them stay valid when vector grows
+#[allow(clippy::vec_box)]
+type Timers = Lock<Vec<Box<Timer>>>;
+
+///Scope for timers, which callbacks can borrow non-`'static` data.
+///
+///Created with `scope`.
+pub struct Scope<'scope, 'env: 'scope> {
+    timers: Timers,
+    //Invariant lifetimes, same as in `std::thread::Scope`
+    _scope: PhantomData<&'scope mut &'scope ()>,
+    _env: PhantomData<&'env mut &'env ()>,
+}
+
+impl<'scope, 'env> Scope<'scope, 'env> {
+    ///Creates new timer, invoking `cb` on expiration, which lives until end of scope.
+    ///
+    ///On failure, returns `None`
+    pub fn timer<F: FnMut() + Send + 'scope>(&'scope self, cb: F) -> Option<&'scope Timer> {
+        //Scope closes every timer before data, borrowed by callback, goes out of scope.
+        let timer = Timer::new(unsafe {
+            Callback::closure_unchecked(cb)
+        })?;
+        let timer = Box::new(timer);
+        let ptr = &*timer as *const Timer;
+        self.timers.lock().push(timer);
+
+        //Boxed timer is only dropped together with scope.
+        Some(unsafe {
+            &*ptr
+        })
+    }
+}
+
+///Closes timers of the scope on exit, including unwinding.
+struct CloseGuard<'a>(&'a Timers);
+
+impl Drop for CloseGuard<'_> {
+    fn drop(&mut self) {
+        let timers = core::mem::take(&mut *self.0.lock());
+        for timer in timers.iter() {
+            timer.cancel();
+        }
+        for timer in timers.iter() {
+            timer.close();
+        }
+    }
+}
+
+///Creates scope for timers, which callbacks can borrow data from enclosing function.
+///
+///Every timer created within scope is cancelled, and its running callbacks finished, before returning.
+pub fn scope<'env, F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> R, R>(f: F) -> R {
+    let scope = Scope {
+        timers: Lock::new(Vec::new()),
+        _scope: PhantomData,
+        _env: PhantomData,
+    };
+
+    let _guard = CloseGuard(&scope.timers);
+
+    f(&scope)
+}
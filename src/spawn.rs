@@ -0,0 +1,41 @@
+use core::{cmp, time};
+
+use crate::{Timer, Callback};
+
+extern crate alloc;
+use alloc::boxed::Box;
+
+///Runs `job` once `timeout` passes, on timer's thread.
+///
+///Timer owns itself and is deleted after firing, so there is no handle to keep alive.
+///
+///Zero `timeout` is treated as shortest possible one.
+///
+///Returns `false` if timer cannot be created or scheduled, in which case `job` is dropped.
+pub fn spawn_after<F: FnOnce() + Send + 'static>(timeout: time::Duration, job: F) -> bool {
+    let timer = Box::into_raw(Box::new(unsafe {
+        Timer::uninit()
+    }));
+
+    let ptr = timer as usize;
+    let on_expire = move || {
+        job();
+        //Timer can be dropped within its own callback
+        drop(unsafe {
+            Box::from_raw(ptr as *mut Timer)
+        });
+    };
+
+    let timer = unsafe {
+        &*timer
+    };
+
+    if timer.init(Callback::once(on_expire)) && timer.schedule_once(cmp::max(timeout, time::Duration::from_nanos(1))) {
+        return true;
+    }
+
+    drop(unsafe {
+        Box::from_raw(ptr as *mut Timer)
+    });
+    false
+}
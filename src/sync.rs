@@ -0,0 +1,124 @@
+//! Internal synchronization primitives
+
+use core::{ptr, ops};
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+
+extern crate alloc;
+use alloc::boxed::Box;
+
+///Minimal spin lock.
+///
+///Only intended for short critical sections.
+pub(crate) struct Lock<T> {
+    locked: AtomicBool,
+    value: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Send for Lock<T> {}
+unsafe impl<T: Send> Sync for Lock<T> {}
+
+impl<T> Lock<T> {
+    #[inline(always)]
+    pub(crate) const fn new(value: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub(crate) fn lock(&self) -> LockGuard<'_, T> {
+        while self.locked.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            while self.locked.load(Ordering::Relaxed) {
+                core::hint::spin_loop();
+            }
+        }
+
+        LockGuard {
+            lock: self,
+        }
+    }
+}
+
+pub(crate) struct LockGuard<'a, T> {
+    lock: &'a Lock<T>,
+}
+
+impl<T> ops::Deref for LockGuard<'_, T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        unsafe {
+            &*self.lock.value.get()
+        }
+    }
+}
+
+impl<T> ops::DerefMut for LockGuard<'_, T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe {
+            &mut *self.lock.value.get()
+        }
+    }
+}
+
+impl<T> Drop for LockGuard<'_, T> {
+    #[inline(always)]
+    fn drop(&mut self) {
+        self.lock.locked.store(false, Ordering::Release);
+    }
+}
+
+///Single value storage, which can be atomically replaced or taken.
+pub(crate) struct Slot<T> {
+    ptr: AtomicPtr<T>,
+}
+
+unsafe impl<T: Send> Send for Slot<T> {}
+unsafe impl<T: Send> Sync for Slot<T> {}
+
+impl<T> Slot<T> {
+    #[inline(always)]
+    pub(crate) const fn new() -> Self {
+        Self {
+            ptr: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    #[inline]
+    ///Stores `value`, dropping previous one, if any.
+    pub(crate) fn put(&self, value: T) {
+        let old = self.ptr.swap(Box::into_raw(Box::new(value)), Ordering::AcqRel);
+        if !old.is_null() {
+            unsafe {
+                let _ = Box::from_raw(old);
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn take(&self) -> Option<T> {
+        let old = self.ptr.swap(ptr::null_mut(), Ordering::AcqRel);
+        if old.is_null() {
+            None
+        } else {
+            unsafe {
+                Some(*Box::from_raw(old))
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.ptr.load(Ordering::Acquire).is_null()
+    }
+}
+
+impl<T> Drop for Slot<T> {
+    #[inline(always)]
+    fn drop(&mut self) {
+        let _ = self.take();
+    }
+}
@@ -1,13 +1,13 @@
 use core::{time, mem, ptr};
-use core::cell::Cell;
-use core::sync::atomic::{AtomicPtr, AtomicBool, Ordering};
-use super::{FatPtr, BoxFnPtr};
+use core::sync::atomic::{AtomicBool, Ordering};
+use super::State;
+use super::backend::{TimerBackend, TimerContext};
 
 extern crate alloc;
 use alloc::boxed::Box;
 
 #[allow(non_camel_case_types)]
-mod ffi {
+pub(crate) mod ffi {
     pub use core::ffi::c_void;
     type uintptr_t = usize;
     type c_long = i64;
@@ -23,6 +23,13 @@ mod ffi {
     pub const DISPATCH_TIME_FOREVER: dispatch_time_t = !0;
     //pub const DISPATCH_WALLTIME_NOW: dispatch_time_t = !1;
     pub const QOS_CLASS_DEFAULT: c_long = 0x15;
+    pub const CLOCK_MONOTONIC: u32 = 6;
+
+    #[repr(C)]
+    pub struct timespec {
+        pub tv_sec: c_long,
+        pub tv_nsec: c_long,
+    }
 
     extern "C" {
         pub static _dispatch_source_type_timer: c_long;
@@ -37,10 +44,12 @@ mod ffi {
         pub fn dispatch_release(object: dispatch_object_t);
         pub fn dispatch_source_cancel(object: dispatch_object_t);
         pub fn dispatch_walltime(when: *const c_void, delta: i64) -> dispatch_time_t;
+        pub fn clock_gettime(clock_id: u32, tp: *mut timespec) -> i32;
+        pub fn pthread_self() -> *mut c_void;
     }
 }
 
-unsafe extern "C" fn timer_callback(data: *mut ffi::c_void) {
+pub(crate) unsafe extern "C" fn timer_callback(data: *mut ffi::c_void) {
     if !data.is_null() {
         let cb: fn() -> () = mem::transmute(data);
 
@@ -48,7 +57,7 @@ unsafe extern "C" fn timer_callback(data: *mut ffi::c_void) {
     }
 }
 
-unsafe extern "C" fn timer_callback_unsafe(data: *mut ffi::c_void) {
+pub(crate) unsafe extern "C" fn timer_callback_unsafe(data: *mut ffi::c_void) {
     if !data.is_null() {
         let cb: unsafe fn() -> () = mem::transmute(data);
 
@@ -56,7 +65,7 @@ unsafe extern "C" fn timer_callback_unsafe(data: *mut ffi::c_void) {
     }
 }
 
-unsafe extern "C" fn timer_callback_generic<T: FnMut() -> ()>(data: *mut ffi::c_void) {
+pub(crate) unsafe extern "C" fn timer_callback_generic<T: FnMut() -> ()>(data: *mut ffi::c_void) {
     if !data.is_null() {
         let cb = &mut *(data as *mut T);
 
@@ -64,314 +73,167 @@ unsafe extern "C" fn timer_callback_generic<T: FnMut() -> ()>(data: *mut ffi::c_
     }
 }
 
-enum CallbackVariant {
-    Trivial(*mut ffi::c_void),
-    Boxed(Box<dyn FnMut()>),
-}
-
-///Timer's callback abstraction
-pub struct Callback {
-    variant: CallbackVariant,
-    ffi_cb: ffi::Callback,
-}
-
-impl Callback {
-    ///Creates raw callback for platform timer.
-    ///
-    ///Signature depends on platform.
-    pub unsafe fn raw(ffi_cb: ffi::Callback, data: *mut ffi::c_void) -> Self {
-        Self {
-            variant: CallbackVariant::Trivial(data),
-            ffi_cb,
-        }
-    }
-
-    ///Creates callback using plain rust function
-    pub fn plain(cb: fn()) -> Self {
-        Self {
-            variant: CallbackVariant::Trivial(cb as _),
-            ffi_cb: timer_callback,
-        }
-    }
-
-    ///Creates callback using plain unsafe function
-    pub fn unsafe_plain(cb: unsafe fn()) -> Self {
-        Self {
-            variant: CallbackVariant::Trivial(cb as _),
-            ffi_cb: timer_callback_unsafe,
-        }
-    }
-
-    ///Creates callback using closure, storing it on heap.
-    pub fn closure<F: 'static + FnMut()>(cb: F) -> Self {
-        Self {
-            variant: CallbackVariant::Boxed(Box::new(cb)),
-            ffi_cb: timer_callback_generic::<F>,
-        }
-    }
+unsafe extern "C" fn state_callback(ctx: *mut ffi::c_void) {
+    State::on_fire(ctx as usize, |cb, data| {
+        (cb)(data)
+    });
 }
 
-///Apple source dispatch timer.
-pub struct Timer {
-    inner: AtomicPtr<ffi::c_void>,
-    //Suspension count. Incremented on suspend, and decremented on each resume
+///Dispatch source, used as timer's handle.
+struct Source {
+    handle: ffi::dispatch_source_t,
+    //Whether source is suspended.
+    //Dispatch source suspension is counted, so we only allow to suspend it once.
     suspend: AtomicBool,
-    data: Cell<BoxFnPtr>,
 }
 
-impl Timer {
-    #[inline]
-    ///Creates new uninitialized instance.
-    ///
-    ///In order to use it one must call `init`.
-    pub const unsafe fn uninit() -> Self {
-        Self {
-            inner: AtomicPtr::new(ptr::null_mut()),
-            //Note timer is created suspended.
-            suspend: AtomicBool::new(true),
-            data: Cell::new(BoxFnPtr::null()),
-        }
-    }
-
+impl Source {
     #[inline(always)]
-    fn get_inner(&self) -> *mut ffi::c_void {
-        let inner = self.inner.load(Ordering::Acquire);
-        debug_assert!(!inner.is_null(), "Timer has not been initialized");
-        inner
+    unsafe fn from_handle<'a>(handle: usize) -> &'a Self {
+        &*(handle as *const Self)
     }
 
     fn suspend(&self) {
         if let Ok(false) = self.suspend.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst) {
-            let handle = self.get_inner();
             unsafe {
-                ffi::dispatch_suspend(handle);
+                ffi::dispatch_suspend(self.handle);
             }
         }
     }
 
     fn resume(&self) {
         if let Ok(true) = self.suspend.compare_exchange(true, false, Ordering::SeqCst, Ordering::SeqCst) {
-            let handle = self.get_inner();
             unsafe {
-                ffi::dispatch_resume(handle);
+                ffi::dispatch_resume(self.handle);
             }
         }
     }
+}
 
-    #[inline(always)]
-    ///Returns whether timer is initialized
-    pub fn is_init(&self) -> bool {
-        !self.inner.load(Ordering::Acquire).is_null()
-    }
+///Invokes timer's callback, same as on expiration.
+pub(crate) unsafe fn dispatch(context: usize) {
+    state_callback(context as *mut ffi::c_void)
+}
 
-    #[must_use]
-    ///Performs timer initialization
-    ///
-    ///`cb` pointer to function to invoke when timer expires.
-    ///
-    ///Returns whether timer has been initialized successfully or not.
-    ///
-    ///If timer is already initialized does nothing, returning false.
-    pub fn init(&self, cb: Callback) -> bool {
-        if self.is_init() {
-            return false;
-        }
+#[inline(always)]
+///Returns `dispatch_source_t` of the timer.
+pub(crate) unsafe fn as_raw(handle: usize) -> *mut ffi::c_void {
+    Source::from_handle(handle).handle as _
+}
 
-        let handle = unsafe {
-            let queue = ffi::dispatch_get_global_queue(ffi::QOS_CLASS_DEFAULT, 0);
-            ffi::dispatch_source_create(&ffi::_dispatch_source_type_timer as *const _ as ffi::dispatch_source_type_t, 0, 0, queue)
-        };
+///Takes ownership of suspended `dispatch_source_t`, created elsewhere.
+pub(crate) unsafe fn adopt(raw: *mut ffi::c_void) -> usize {
+    Box::into_raw(Box::new(Source {
+        handle: raw as ffi::dispatch_source_t,
+        suspend: AtomicBool::new(true),
+    })) as usize
+}
 
-        match self.inner.compare_exchange(ptr::null_mut(), handle as _, Ordering::SeqCst, Ordering::Acquire) {
-            Ok(_) => match handle.is_null() {
-                true => false,
-                false => {
-                    let ffi_cb = cb.ffi_cb;
-                    let (data, ffi_data) = match cb.variant {
-                        CallbackVariant::Trivial(data) => (FatPtr::null(), data),
-                        CallbackVariant::Boxed(cb) => unsafe {
-                            let raw = Box::into_raw(cb);
-                            (mem::transmute(raw), raw as *mut ffi::c_void)
-                        },
-                    };
-
-                    unsafe {
-                        ffi::dispatch_source_set_event_handler_f(handle, ffi_cb);
-                        ffi::dispatch_set_context(handle, ffi_data);
-                    }
-                    self.data.set(BoxFnPtr(data));
-                    true
-                }
-            },
-            Err(_) => {
-                unsafe {
-                    ffi::dispatch_release(handle);
-                }
-                false
-            }
-        }
-    }
+#[inline(always)]
+///Returns remaining time until next alarm and interval of armed timer, which OS doesn't report.
+pub(crate) unsafe fn remaining(_handle: usize) -> Option<(time::Duration, time::Duration)> {
+    None
+}
 
+#[inline]
+///Disarms timer, returning remaining time until next alarm and interval it had, which OS doesn't report.
+pub(crate) unsafe fn pause(handle: usize) -> Option<(time::Duration, time::Duration)> {
+    BACKEND.disarm(handle);
+    None
+}
 
-    ///Creates new timer, invoking provided `cb` when timer expires.
-    ///
-    ///On failure, returns `None`
-    pub fn new(cb: Callback) -> Option<Self> {
-        let handle = unsafe {
-            let queue = ffi::dispatch_get_global_queue(ffi::QOS_CLASS_DEFAULT, 0);
-            ffi::dispatch_source_create(&ffi::_dispatch_source_type_timer as *const _ as ffi::dispatch_source_type_t, 0, 0, queue)
-        };
+#[inline(always)]
+///Applies `leeway` to callback's thread, not needed as it is passed to the timer.
+pub(crate) fn apply_leeway(_leeway: time::Duration) {
+}
 
-        if handle.is_null() {
-            return None;
-        }
+///Dispatch sources backend.
+pub struct Apple;
 
-        let ffi_cb = cb.ffi_cb;
-        let (data, ffi_data) = match cb.variant {
-            CallbackVariant::Trivial(data) => (FatPtr::null(), data),
-            CallbackVariant::Boxed(cb) => unsafe {
-                let raw = Box::into_raw(cb);
-                (mem::transmute(raw), raw as *mut ffi::c_void)
-            },
-        };
+///Backend, used by timers.
+pub(crate) const BACKEND: Apple = Apple;
 
+unsafe impl TimerBackend for Apple {
+    fn now(&self) -> time::Duration {
+        let mut ts = ffi::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
         unsafe {
-            ffi::dispatch_source_set_event_handler_f(handle, ffi_cb);
-            ffi::dispatch_set_context(handle, ffi_data);
+            ffi::clock_gettime(ffi::CLOCK_MONOTONIC, &mut ts);
         }
 
-        Some(Self {
-            inner: AtomicPtr::new(handle as _),
-            suspend: AtomicBool::new(true),
-            data: Cell::new(BoxFnPtr(data)),
-        })
+        time::Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
     }
 
-    ///Schedules timer to alarm once after `timeout` passes.
-    ///
-    ///Note that if timer has been scheduled before, but hasn't expire yet, it shall be cancelled.
-    ///To prevent that user must `cancel` timer first.
-    ///
-    ///Also due to dispatch API limitations, `timeout` is truncated by `i64::max_value()`
-    pub fn schedule_once(&self, timeout: time::Duration) {
-        let handle = self.get_inner();
-
-        self.suspend();
+    unsafe fn create(&self, context: TimerContext) -> usize {
+        let queue = ffi::dispatch_get_global_queue(ffi::QOS_CLASS_DEFAULT, 0);
+        let handle = ffi::dispatch_source_create(&ffi::_dispatch_source_type_timer as *const _ as ffi::dispatch_source_type_t, 0, 0, queue);
 
-        unsafe {
-            let start = ffi::dispatch_walltime(ptr::null(), timeout.as_nanos() as i64);
-            ffi::dispatch_source_set_timer(handle, start, ffi::DISPATCH_TIME_FOREVER, 0);
+        if handle.is_null() {
+            return 0;
         }
 
-        self.resume();
-    }
+        ffi::dispatch_source_set_event_handler_f(handle, state_callback);
+        ffi::dispatch_set_context(handle, context.get() as *mut ffi::c_void);
 
-    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`.
-    ///
-    ///Note that if timer has been scheduled before, but hasn't expire yet, behaviour is undefined (Callback may or may not be called).
-    ///To prevent that user must `cancel` timer first.
-    ///
-    ///# Note
-    ///
-    ///- `timeout` is truncated by `i64::max_value()`
-    ///- `interval` is truncated by `u64::max_value()`
-    ///
-    ///Returns `true` if successfully set, otherwise on error returns `false`
-    pub fn schedule_interval(&self, timeout: time::Duration, interval: time::Duration) -> bool {
-        let handle = self.get_inner();
-
-        self.suspend();
+        Box::into_raw(Box::new(Source {
+            handle,
+            //Note timer is created suspended.
+            suspend: AtomicBool::new(true),
+        })) as usize
+    }
 
-        unsafe {
-            let start = ffi::dispatch_walltime(ptr::null(), timeout.as_nanos() as i64);
-            ffi::dispatch_source_set_timer(handle, start, interval.as_nanos() as _, 0);
+    ///`timeout` is truncated by `i64::max_value()`, `interval` and `leeway` by `u64::max_value()`
+    unsafe fn set(&self, handle: usize, timeout: time::Duration, interval: time::Duration, leeway: time::Duration) -> bool {
+        //OS fires immediately on zero timeout
+        if timeout == time::Duration::ZERO {
+            self.disarm(handle);
+            return true;
         }
 
-        self.resume();
+        let source = Source::from_handle(handle);
+
+        source.suspend();
+
+        let start = ffi::dispatch_walltime(ptr::null(), timeout.as_nanos() as i64);
+        let interval = match interval == time::Duration::ZERO {
+            true => ffi::DISPATCH_TIME_FOREVER,
+            false => interval.as_nanos() as _,
+        };
+        ffi::dispatch_source_set_timer(source.handle, start, interval, leeway.as_nanos() as u64);
+
+        source.resume();
 
         true
     }
 
     #[inline]
-    ///Returns `true` if timer has been scheduled and still pending.
-    ///
-    ///On Win/Mac it only returns whether timer has been scheduled, as there is no way to check
-    ///whether timer is ongoing
-    pub fn is_scheduled(&self) -> bool {
-        !self.suspend.load(Ordering::Acquire)
+    ///Returns `true` if timer has been armed.
+    unsafe fn get(&self, handle: usize) -> bool {
+        !Source::from_handle(handle).suspend.load(Ordering::Acquire)
     }
 
     #[inline]
-    ///Cancels ongoing timer, if it was scheduled.
-    pub fn cancel(&self) {
-        self.suspend()
+    unsafe fn disarm(&self, handle: usize) {
+        Source::from_handle(handle).suspend()
     }
-}
 
-impl Drop for Timer {
-    fn drop(&mut self) {
-        let handle = self.inner.load(Ordering::Relaxed);
-        if !handle.is_null() {
-            unsafe {
-                ffi::dispatch_source_cancel(handle);
+    unsafe fn delete(&self, handle: usize) {
+        let source = Box::from_raw(handle as *mut Source);
+        ffi::dispatch_source_cancel(source.handle);
 
-                //It is error to release while source is suspended
-                //So we decrement it
-                self.resume();
+        //It is error to release while source is suspended
+        //So we decrement it
+        source.resume();
 
-                ffi::dispatch_release(handle);
-            }
-        }
+        ffi::dispatch_release(source.handle);
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn init_plain_fn() {
-        let mut timer = unsafe {
-            Timer::uninit()
-        };
-
-        fn cb() {
-        }
-
-        let closure = || {
-        };
 
-        assert!(timer.init(Callback::plain(cb)));
-        let ptr = timer.inner.load(Ordering::Relaxed);
-        assert!(!ptr.is_null());
-        assert!(timer.data.get_mut().is_null());
-
-        assert!(!timer.init(Callback::closure(closure)));
-        assert!(!ptr.is_null());
-        assert_eq!(ptr, timer.inner.load(Ordering::Relaxed));
-        assert!(timer.data.get_mut().is_null());
-    }
-
-    #[test]
-    fn init_closure() {
-        let mut timer = unsafe {
-            Timer::uninit()
-        };
-
-        fn cb() {
+    #[inline(always)]
+    fn thread_id(&self) -> usize {
+        unsafe {
+            ffi::pthread_self() as usize
         }
-
-        let closure = || {
-        };
-
-        assert!(timer.init(Callback::closure(closure)));
-        let ptr = timer.inner.load(Ordering::Relaxed);
-        assert!(!ptr.is_null());
-        assert!(!timer.data.get_mut().is_null());
-
-        assert!(!timer.init(Callback::plain(cb)));
-        assert!(!ptr.is_null());
-        assert_eq!(ptr, timer.inner.load(Ordering::Relaxed));
-        assert!(!timer.data.get_mut().is_null());
     }
 }
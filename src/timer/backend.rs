@@ -0,0 +1,108 @@
+//! Timer backends
+//!
+//! Backend is selected at compile time:
+//!
+//! - POSIX timers on unix;
+//! - Thread pool timers on Windows;
+//! - Dispatch sources on Mac;
+//! - Backend, installed with `install`, on other targets or when `custom-backend` feature is enabled.
+
+use core::time;
+
+use super::sys;
+
+#[derive(Clone, Copy)]
+///Context of the timer, passed to the backend on creation.
+pub struct TimerContext(usize);
+
+impl TimerContext {
+    #[inline(always)]
+    pub(crate) fn new(context: usize) -> Self {
+        Self(context)
+    }
+
+    #[cfg(not(os_timer_backend = "custom"))]
+    #[inline(always)]
+    pub(crate) fn get(&self) -> usize {
+        self.0
+    }
+
+    #[inline]
+    ///Invokes timer's callback.
+    ///
+    ///Backend must call it on every expiration of the timer.
+    ///
+    ///# Safety
+    ///
+    ///Must not be called from within timer's callback.
+    pub unsafe fn fire(&self) {
+        sys::dispatch(self.0)
+    }
+}
+
+///Interface of the platform timer.
+///
+///Timer is identified by non-zero handle, returned by `create`.
+///
+///# Safety
+///
+///Implementation must fire timer only while it is armed and not yet deleted, and `thread_id` must identify calling thread.
+pub unsafe trait TimerBackend: Sync {
+    ///Returns current value of monotonic clock.
+    fn now(&self) -> time::Duration;
+
+    ///Creates disarmed timer, which calls `context.fire()` on each expiration.
+    ///
+    ///Returns zero on failure.
+    unsafe fn create(&self, context: TimerContext) -> usize;
+
+    ///Arms timer to alarm after `timeout` and then periodically with `interval`, unless it is zero.
+    ///
+    ///Zero `timeout` disarms timer.
+    ///`leeway` is time by which alarm can be deferred, if supported.
+    unsafe fn set(&self, handle: usize, timeout: time::Duration, interval: time::Duration, leeway: time::Duration) -> bool;
+
+    ///Returns whether timer is armed.
+    unsafe fn get(&self, handle: usize) -> bool;
+
+    #[inline]
+    ///Disarms timer, without waiting for running callbacks.
+    unsafe fn disarm(&self, handle: usize) {
+        self.set(handle, time::Duration::ZERO, time::Duration::ZERO, time::Duration::ZERO);
+    }
+
+    #[inline]
+    ///Waits for running callbacks to finish, if supported.
+    unsafe fn wait(&self, _handle: usize) {
+    }
+
+    ///Deletes timer.
+    unsafe fn delete(&self, handle: usize);
+
+    ///Returns identifier of current thread.
+    ///
+    ///Identifiers of different threads, that are alive at the same time, must differ, as timer relies on it
+    ///to never wait for callback from within itself.
+    fn thread_id(&self) -> usize;
+}
+
+#[cfg(os_timer_backend = "posix")]
+///Platform's backend.
+pub use super::posix::Posix as Os;
+#[cfg(os_timer_backend = "win32")]
+///Platform's backend.
+pub use super::win32::Win32 as Os;
+#[cfg(os_timer_backend = "apple")]
+///Platform's backend.
+pub use super::apple::Apple as Os;
+
+#[cfg(os_timer_backend = "custom")]
+#[inline]
+///Installs backend, used by all timers.
+///
+///Must be called before creating any timer, otherwise timer creation fails.
+///
+///Returns `false` if backend is already installed.
+pub fn install(backend: &'static dyn TimerBackend) -> bool {
+    sys::install(backend)
+}
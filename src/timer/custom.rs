@@ -0,0 +1,164 @@
+use core::{ptr, time, mem};
+use core::sync::atomic::{AtomicPtr, Ordering};
+use super::State;
+use super::backend::{TimerBackend, TimerContext};
+
+extern crate alloc;
+use alloc::boxed::Box;
+
+pub(crate) mod ffi {
+    pub use core::ffi::c_void;
+
+    pub type Callback = unsafe extern "C" fn(*mut c_void);
+}
+
+pub(crate) unsafe extern "C" fn timer_callback(data: *mut ffi::c_void) {
+    if !data.is_null() {
+        let cb: fn() -> () = mem::transmute(data);
+
+        (cb)();
+    }
+}
+
+pub(crate) unsafe extern "C" fn timer_callback_unsafe(data: *mut ffi::c_void) {
+    if !data.is_null() {
+        let cb: unsafe fn() -> () = mem::transmute(data);
+
+        (cb)();
+    }
+}
+
+pub(crate) unsafe extern "C" fn timer_callback_generic<T: FnMut() -> ()>(data: *mut ffi::c_void) {
+    if !data.is_null() {
+        let cb = &mut *(data as *mut T);
+
+        (cb)();
+    }
+}
+
+static INSTALLED: AtomicPtr<&'static dyn TimerBackend> = AtomicPtr::new(ptr::null_mut());
+
+///Installs backend, returning `false` if it is already installed.
+pub(crate) fn install(backend: &'static dyn TimerBackend) -> bool {
+    let backend = Box::into_raw(Box::new(backend));
+    match INSTALLED.compare_exchange(ptr::null_mut(), backend, Ordering::AcqRel, Ordering::Acquire) {
+        Ok(_) => true,
+        Err(_) => {
+            drop(unsafe {
+                Box::from_raw(backend)
+            });
+            false
+        },
+    }
+}
+
+#[inline(always)]
+fn backend() -> Option<&'static dyn TimerBackend> {
+    let backend = INSTALLED.load(Ordering::Acquire);
+    match backend.is_null() {
+        true => None,
+        //Installed backend is never released
+        false => Some(unsafe {
+            *backend
+        }),
+    }
+}
+
+#[inline(always)]
+///Returns installed backend, which must exist for any timer to be created.
+fn installed() -> &'static dyn TimerBackend {
+    backend().expect("Timer backend is not installed")
+}
+
+pub(crate) unsafe fn dispatch(context: usize) {
+    State::on_fire(context, |cb, data| {
+        (cb)(data)
+    });
+}
+
+#[inline(always)]
+///Returns backend's handle of the timer.
+pub(crate) unsafe fn as_raw(handle: usize) -> *mut ffi::c_void {
+    handle as _
+}
+
+#[inline(always)]
+///Takes ownership of backend's handle, created elsewhere.
+pub(crate) unsafe fn adopt(raw: *mut ffi::c_void) -> usize {
+    raw as usize
+}
+
+#[inline(always)]
+///Leeway is passed to backend's `set`.
+pub(crate) fn apply_leeway(_leeway: time::Duration) {
+}
+
+#[inline(always)]
+///Returns remaining time until next alarm and interval of armed timer, which backend doesn't report.
+pub(crate) unsafe fn remaining(_handle: usize) -> Option<(time::Duration, time::Duration)> {
+    None
+}
+
+#[inline]
+///Disarms timer, returning remaining time until next alarm and interval it had, which backend doesn't report.
+pub(crate) unsafe fn pause(handle: usize) -> Option<(time::Duration, time::Duration)> {
+    BACKEND.disarm(handle);
+    None
+}
+
+///Forwards to installed backend.
+pub(crate) struct Installed;
+
+///Backend, used by timers.
+pub(crate) const BACKEND: Installed = Installed;
+
+unsafe impl TimerBackend for Installed {
+    ///Returns current value of backend's clock, or zero if it is not installed yet.
+    fn now(&self) -> time::Duration {
+        match backend() {
+            Some(backend) => backend.now(),
+            None => time::Duration::ZERO,
+        }
+    }
+
+    ///Returns zero if backend is not installed.
+    unsafe fn create(&self, context: TimerContext) -> usize {
+        match backend() {
+            Some(backend) => backend.create(context),
+            None => 0,
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn set(&self, handle: usize, timeout: time::Duration, interval: time::Duration, leeway: time::Duration) -> bool {
+        installed().set(handle, timeout, interval, leeway)
+    }
+
+    #[inline(always)]
+    unsafe fn get(&self, handle: usize) -> bool {
+        installed().get(handle)
+    }
+
+    #[inline(always)]
+    unsafe fn disarm(&self, handle: usize) {
+        installed().disarm(handle)
+    }
+
+    #[inline(always)]
+    unsafe fn wait(&self, handle: usize) {
+        installed().wait(handle)
+    }
+
+    #[inline(always)]
+    unsafe fn delete(&self, handle: usize) {
+        installed().delete(handle)
+    }
+
+    #[inline(always)]
+    fn thread_id(&self) -> usize {
+        match backend() {
+            Some(backend) => backend.thread_id(),
+            None => 0,
+        }
+    }
+}
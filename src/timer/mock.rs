@@ -0,0 +1,225 @@
+//! Virtual clock, replacing OS timers
+//!
+//! Time only moves with `advance`, which invokes expired callbacks synchronously on caller's thread.
+//! Clock is global, so tests relying on it should not run concurrently.
+
+use core::{time, mem};
+use super::State;
+use super::backend::{TimerBackend, TimerContext};
+use crate::sync::Lock;
+
+extern crate alloc;
+use alloc::collections::BTreeMap;
+
+pub(crate) mod ffi {
+    pub use core::ffi::c_void;
+
+    pub type Callback = unsafe extern "C" fn(*mut c_void);
+}
+
+pub(crate) unsafe extern "C" fn timer_callback(data: *mut ffi::c_void) {
+    if !data.is_null() {
+        let cb: fn() -> () = mem::transmute(data);
+
+        (cb)();
+    }
+}
+
+pub(crate) unsafe extern "C" fn timer_callback_unsafe(data: *mut ffi::c_void) {
+    if !data.is_null() {
+        let cb: unsafe fn() -> () = mem::transmute(data);
+
+        (cb)();
+    }
+}
+
+pub(crate) unsafe extern "C" fn timer_callback_generic<T: FnMut() -> ()>(data: *mut ffi::c_void) {
+    if !data.is_null() {
+        let cb = &mut *(data as *mut T);
+
+        (cb)();
+    }
+}
+
+struct Entry {
+    context: usize,
+    //Next alarm and interval, while armed
+    armed: Option<(time::Duration, time::Duration)>,
+}
+
+struct Clock {
+    now: time::Duration,
+    next_id: usize,
+    timers: BTreeMap<usize, Entry>,
+}
+
+static CLOCK: Lock<Clock> = Lock::new(Clock {
+    now: time::Duration::ZERO,
+    next_id: 1,
+    timers: BTreeMap::new(),
+});
+
+///Returns current time of virtual clock.
+pub fn now() -> time::Duration {
+    CLOCK.lock().now
+}
+
+///Moves virtual clock forward by `duration`, invoking every timer that expires meanwhile.
+///
+///Timers fire in order of their deadlines, and timers with the same deadline in order of creation.
+///Periodic timer fires as many times as its interval fits into `duration`.
+pub fn advance(duration: time::Duration) {
+    let target = CLOCK.lock().now.saturating_add(duration);
+
+    loop {
+        let context = {
+            let mut clock = CLOCK.lock();
+            let next = clock.timers.iter().filter_map(|(id, entry)| match entry.armed {
+                Some((deadline, _)) if deadline <= target => Some((deadline, *id)),
+                _ => None,
+            }).min();
+
+            let (deadline, id) = match next {
+                Some(next) => next,
+                None => {
+                    clock.now = target;
+                    break;
+                }
+            };
+
+            clock.now = core::cmp::max(clock.now, deadline);
+            let entry = clock.timers.get_mut(&id).expect("To have expired timer");
+            entry.armed = match entry.armed {
+                Some((deadline, interval)) if interval != time::Duration::ZERO => Some((deadline + interval, interval)),
+                _ => None,
+            };
+            entry.context
+        };
+
+        unsafe {
+            dispatch(context);
+        }
+    }
+}
+
+///Invokes timer's callback, same as on expiration.
+pub(crate) unsafe fn dispatch(context: usize) {
+    State::on_fire(context, |cb, data| {
+        (cb)(data)
+    });
+}
+
+#[inline(always)]
+///Returns identifier of the timer.
+pub(crate) unsafe fn as_raw(handle: usize) -> *mut ffi::c_void {
+    handle as _
+}
+
+#[inline(always)]
+///Takes ownership of the timer, created elsewhere.
+pub(crate) unsafe fn adopt(raw: *mut ffi::c_void) -> usize {
+    raw as usize
+}
+
+#[inline(always)]
+///Leeway has no meaning for virtual clock.
+pub(crate) fn apply_leeway(_leeway: time::Duration) {
+}
+
+///Returns remaining time until next alarm and interval of armed timer.
+pub(crate) unsafe fn remaining(handle: usize) -> Option<(time::Duration, time::Duration)> {
+    let clock = CLOCK.lock();
+    match clock.timers.get(&handle) {
+        Some(Entry { armed: Some((deadline, interval)), .. }) => Some((*deadline - clock.now, *interval)),
+        _ => None,
+    }
+}
+
+///Disarms timer, returning remaining time until next alarm and interval it had.
+pub(crate) unsafe fn pause(handle: usize) -> Option<(time::Duration, time::Duration)> {
+    let mut clock = CLOCK.lock();
+    let now = clock.now;
+    match clock.timers.get_mut(&handle).and_then(|entry| entry.armed.take()) {
+        Some((deadline, interval)) => Some((deadline - now, interval)),
+        None => None,
+    }
+}
+
+///Virtual clock backend.
+pub(crate) struct Mock;
+
+///Backend, used by timers.
+pub(crate) const BACKEND: Mock = Mock;
+
+unsafe impl TimerBackend for Mock {
+    #[inline(always)]
+    fn now(&self) -> time::Duration {
+        now()
+    }
+
+    unsafe fn create(&self, context: TimerContext) -> usize {
+        let mut clock = CLOCK.lock();
+        let id = clock.next_id;
+        clock.next_id += 1;
+        clock.timers.insert(id, Entry {
+            context: context.get(),
+            armed: None,
+        });
+        id
+    }
+
+    unsafe fn set(&self, handle: usize, timeout: time::Duration, interval: time::Duration, _leeway: time::Duration) -> bool {
+        let mut clock = CLOCK.lock();
+        let now = clock.now;
+        match clock.timers.get_mut(&handle) {
+            Some(entry) => {
+                entry.armed = match timeout == time::Duration::ZERO {
+                    true => None,
+                    false => Some((now + timeout, interval)),
+                };
+                true
+            },
+            None => false,
+        }
+    }
+
+    unsafe fn get(&self, handle: usize) -> bool {
+        match CLOCK.lock().timers.get(&handle) {
+            Some(entry) => entry.armed.is_some(),
+            None => false,
+        }
+    }
+
+    unsafe fn disarm(&self, handle: usize) {
+        if let Some(entry) = CLOCK.lock().timers.get_mut(&handle) {
+            entry.armed = None;
+        }
+    }
+
+    #[inline(always)]
+    ///Callbacks are only invoked within `advance`, so there is nothing to wait for.
+    unsafe fn wait(&self, _handle: usize) {
+    }
+
+    unsafe fn delete(&self, handle: usize) {
+        CLOCK.lock().timers.remove(&handle);
+    }
+
+    #[cfg(feature = "std")]
+    #[inline]
+    ///Uses address of thread local variable.
+    fn thread_id(&self) -> usize {
+        std::thread_local! {
+            static ID: u8 = const { 0 };
+        }
+
+        ID.with(|id| id as *const u8 as usize)
+    }
+
+    #[cfg(not(feature = "std"))]
+    #[inline(always)]
+    ///Threads cannot be distinguished without `std` feature, so timer never waits for callback, running in `advance`.
+    fn thread_id(&self) -> usize {
+        1
+    }
+}
@@ -1,7 +1,13 @@
-use core::{mem,time};
+use core::{mem, ptr, time};
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
 
 extern crate alloc;
 use alloc::boxed::Box;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+
+use crate::sync::Lock;
+use backend::{TimerBackend, TimerContext};
 
 #[derive(PartialEq, Clone, Copy)]
 #[repr(C)]
@@ -25,36 +31,1008 @@ impl FatPtr {
     }
 }
 
-#[cfg(windows)]
+pub mod backend;
+
+#[cfg(os_timer_backend = "win32")]
 mod win32;
-#[cfg(windows)]
-pub use win32::*;
+#[cfg(os_timer_backend = "win32")]
+use win32 as sys;
 
-#[cfg(any(target_os = "macos", target_os = "ios"))]
+#[cfg(os_timer_backend = "apple")]
 mod apple;
-#[cfg(any(target_os = "macos", target_os = "ios"))]
-pub use apple::*;
+#[cfg(os_timer_backend = "apple")]
+use apple as sys;
 
-#[cfg(all(unix, not(any(target_os = "macos", target_os = "ios"))))]
+#[cfg(os_timer_backend = "posix")]
 mod posix;
-#[cfg(all(unix, not(any(target_os = "macos", target_os = "ios"))))]
-pub use posix::*;
+#[cfg(os_timer_backend = "posix")]
+use posix as sys;
+
+#[cfg(os_timer_backend = "mock")]
+pub mod mock;
+#[cfg(os_timer_backend = "mock")]
+use mock as sys;
+
+#[cfg(os_timer_backend = "custom")]
+mod custom;
+#[cfg(os_timer_backend = "custom")]
+use custom as sys;
+
+#[inline(always)]
+///Returns current value of clock, used by timers.
+pub(crate) fn monotonic_now() -> time::Duration {
+    sys::BACKEND.now()
+}
+
+#[inline(always)]
+///Returns identifier of current thread, as reported by backend.
+pub(crate) fn thread_id() -> usize {
+    sys::BACKEND.thread_id()
+}
+
+const MIN_TIMEOUT: time::Duration = time::Duration::from_nanos(1);
+
+enum CallbackVariant {
+    Trivial(*mut sys::ffi::c_void),
+    Boxed(Box<dyn FnMut()>),
+    Shared(SharedData),
+}
+
+///Reference counted data with plain function, invoked without going through FFI callback.
+struct SharedData {
+    data: *const (),
+    cb: *const (),
+    call: unsafe fn(*const (), *const ()),
+    release: unsafe fn(*const ()),
+}
+
+impl SharedData {
+    unsafe fn call_with<T>(cb: *const (), data: *const ()) {
+        let cb: fn(&T) = mem::transmute(cb);
+        (cb)(&*(data as *const T));
+    }
+
+    unsafe fn release_with<T>(data: *const ()) {
+        drop(Arc::from_raw(data as *const T));
+    }
+
+    #[inline(always)]
+    fn call(&self) {
+        unsafe {
+            (self.call)(self.cb, self.data)
+        }
+    }
+}
+
+impl Drop for SharedData {
+    #[inline(always)]
+    fn drop(&mut self) {
+        unsafe {
+            (self.release)(self.data)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///Action to take after callback, created with `Callback::controlled`, returns.
+pub enum Next {
+    ///Keeps current schedule.
+    Continue,
+    ///Cancels timer.
+    Stop,
+    ///Schedules next alarm after duration, keeping interval of periodic timer.
+    RescheduleIn(time::Duration),
+}
+
+///Timer's callback abstraction
+pub struct Callback {
+    variant: CallbackVariant,
+    ffi_cb: sys::ffi::Callback,
+    //Result of controlled callback, written by closure
+    next: Option<Box<Lock<Option<Next>>>>,
+}
+
+impl Callback {
+    ///Creates raw callback for platform timer.
+    ///
+    ///Signature depends on platform.
+    pub unsafe fn raw(ffi_cb: sys::ffi::Callback, data: *mut sys::ffi::c_void) -> Self {
+        Self {
+            variant: CallbackVariant::Trivial(data),
+            ffi_cb,
+            next: None,
+        }
+    }
+
+    ///Creates callback using plain rust function
+    pub fn plain(cb: fn()) -> Self {
+        Self {
+            variant: CallbackVariant::Trivial(cb as _),
+            ffi_cb: sys::timer_callback,
+            next: None,
+        }
+    }
+
+    ///Creates callback using plain unsafe function
+    pub fn unsafe_plain(cb: unsafe fn()) -> Self {
+        Self {
+            variant: CallbackVariant::Trivial(cb as _),
+            ffi_cb: sys::timer_callback_unsafe,
+            next: None,
+        }
+    }
+
+    ///Creates callback using closure, storing it on heap.
+    pub fn closure<F: 'static + FnMut()>(cb: F) -> Self {
+        Self {
+            variant: CallbackVariant::Boxed(Box::new(cb)),
+            ffi_cb: sys::timer_callback_generic::<F>,
+            next: None,
+        }
+    }
+
+    ///Creates callback using plain function, invoked with reference to shared `data`.
+    ///
+    ///Unlike `closure` it requires no allocation, `data` is released when timer is dropped.
+    pub fn with_data<T: Send + Sync + 'static>(data: Arc<T>, cb: fn(&T)) -> Self {
+        Self {
+            variant: CallbackVariant::Shared(SharedData {
+                data: Arc::into_raw(data) as *const (),
+                cb: cb as *const (),
+                call: SharedData::call_with::<T>,
+                release: SharedData::release_with::<T>,
+            }),
+            //Unused, as shared data is invoked directly
+            ffi_cb: sys::timer_callback,
+            next: None,
+        }
+    }
+
+    ///Creates callback using plain function, invoked with `target` as long as it is alive.
+    ///
+    ///Once `target` cannot be upgraded anymore, timer is cancelled instead.
+    pub fn weak<T: Send + Sync + 'static>(target: Weak<T>, cb: fn(&T)) -> Self {
+        Self::controlled(move || match target.upgrade() {
+            Some(target) => {
+                cb(&target);
+                Next::Continue
+            },
+            None => Next::Stop,
+        })
+    }
+
+    ///Creates callback using closure, which is not required to be `'static`.
+    ///
+    ///Caller must guarantee that timer is closed before anything, borrowed by `cb`, goes out of scope.
+    pub(crate) unsafe fn closure_unchecked<F: FnMut()>(cb: F) -> Self {
+        let cb: Box<dyn FnMut() + '_> = Box::new(cb);
+        Self {
+            variant: CallbackVariant::Boxed(mem::transmute::<Box<dyn FnMut() + '_>, Box<dyn FnMut()>>(cb)),
+            ffi_cb: sys::timer_callback_generic::<F>,
+            next: None,
+        }
+    }
+
+    ///Creates callback using closure, which is consumed on first alarm.
+    ///
+    ///Intended for `schedule_once`, any later alarm does nothing.
+    pub fn once<F: 'static + FnOnce() + Send>(cb: F) -> Self {
+        let mut cb = Some(cb);
+        Self::closure(move || {
+            if let Some(cb) = cb.take() {
+                cb();
+            }
+        })
+    }
+
+    ///Creates callback using closure, which decides what to do with timer after each alarm.
+    ///
+    ///`Next` is applied unless timer is re-scheduled or cancelled by callback itself.
+    pub fn controlled<F: 'static + FnMut() -> Next + Send>(mut cb: F) -> Self {
+        let next = Box::new(Lock::new(None));
+        let slot = &*next as *const Lock<Option<Next>> as usize;
+        let mut callback = Self::closure(move || {
+            let result = cb();
+            let slot = unsafe {
+                &*(slot as *const Lock<Option<Next>>)
+            };
+            *slot.lock() = Some(result);
+        });
+        callback.next = Some(next);
+        callback
+    }
+}
+
+#[derive(Clone, Copy)]
+///Random offset, added to each alarm.
+enum Spread {
+    //Up to fixed duration
+    Uniform(time::Duration),
+    //Up to fraction of time until alarm
+    Proportional(f64),
+}
+
+impl Spread {
+    fn offset(&self, timeout: time::Duration) -> time::Duration {
+        let max = match self {
+            Spread::Uniform(max) => *max,
+            Spread::Proportional(ratio) => time::Duration::try_from_secs_f64(timeout.as_secs_f64() * ratio).unwrap_or(time::Duration::MAX),
+        };
+
+        crate::rand::duration_up_to(max)
+    }
+}
+
+#[derive(Clone, Copy)]
+///Options of periodic schedule.
+struct Options {
+    times: Option<u32>,
+    until: Option<time::Duration>,
+    fixed_delay: bool,
+    jitter: Option<Spread>,
+    leeway: time::Duration,
+}
+
+impl Options {
+    const fn new() -> Self {
+        Self {
+            times: None,
+            until: None,
+            fixed_delay: false,
+            jitter: None,
+            leeway: time::Duration::ZERO,
+        }
+    }
+}
+
+///Current schedule of the timer.
+struct Plan {
+    //Incremented on every change of schedule, to detect changes during callback
+    epoch: u32,
+    //Deadline of first alarm and interval, while timer is armed
+    armed: Option<(time::Duration, time::Duration)>,
+    //Remaining timeout and interval of paused timer
+    paused: Option<(time::Duration, time::Duration)>,
+    //Number of expirations left before timer cancels itself
+    fires_left: Option<u32>,
+    //Deadline after which timer cancels itself
+    until: Option<time::Duration>,
+    //Interval of schedule, which is re-armed as one shot on each alarm instead of relying on OS
+    period: Option<time::Duration>,
+    //Whether `period` is re-armed after callback, rather than before
+    fixed_delay: bool,
+    jitter: Option<Spread>,
+    //Deadline of current alarm without jitter, used to keep rate of re-armed schedule
+    nominal: time::Duration,
+    //Delay, by which OS is allowed to defer alarm
+    leeway: time::Duration,
+}
+
+impl Plan {
+    const fn new(epoch: u32) -> Self {
+        Self {
+            epoch,
+            armed: None,
+            paused: None,
+            fires_left: None,
+            until: None,
+            period: None,
+            fixed_delay: false,
+            jitter: None,
+            nominal: time::Duration::ZERO,
+            leeway: time::Duration::ZERO,
+        }
+    }
+
+    ///Arms one shot alarm of re-armed schedule.
+    fn rearm(&mut self, handle: usize, now: time::Duration, timeout: time::Duration) {
+        let timeout = match self.jitter {
+            Some(jitter) => timeout.saturating_add(jitter.offset(timeout)),
+            None => timeout,
+        };
+        //Zero timeout disarms timer
+        let timeout = core::cmp::max(timeout, MIN_TIMEOUT);
+
+        self.armed = Some((now + timeout, time::Duration::ZERO));
+        unsafe {
+            sys::BACKEND.set(handle, timeout, time::Duration::ZERO, self.leeway);
+        }
+    }
+}
+
+///Timer's callback, which can be replaced while timer is running.
+struct Handler {
+    ffi_cb: sys::ffi::Callback,
+    ffi_data: *mut sys::ffi::c_void,
+    //Owns closure, referenced by `ffi_data`
+    #[allow(dead_code)]
+    data: BoxFnPtr,
+    shared: Option<SharedData>,
+    //Owns result of controlled callback, referenced by closure
+    next: Option<Box<Lock<Option<Next>>>>,
+}
+
+impl Handler {
+    fn new(cb: Callback) -> Self {
+        let (data, ffi_data, shared) = match cb.variant {
+            CallbackVariant::Trivial(data) => (BoxFnPtr::null(), data, None),
+            CallbackVariant::Boxed(cb) => unsafe {
+                let raw = Box::into_raw(cb);
+                (BoxFnPtr(mem::transmute::<*mut dyn FnMut(), FatPtr>(raw)), raw as *mut sys::ffi::c_void, None)
+            },
+            CallbackVariant::Shared(shared) => (BoxFnPtr::null(), ptr::null_mut(), Some(shared)),
+        };
+
+        Self {
+            ffi_cb: cb.ffi_cb,
+            ffi_data,
+            data,
+            shared,
+            next: cb.next,
+        }
+    }
+
+    ///Invokes callback, returning action requested by it.
+    fn call<F: FnOnce(sys::ffi::Callback, *mut sys::ffi::c_void)>(&self, invoke: F) -> Next {
+        match &self.shared {
+            Some(shared) => shared.call(),
+            None => invoke(self.ffi_cb, self.ffi_data),
+        }
+
+        match &self.next {
+            Some(next) => next.lock().take().unwrap_or(Next::Continue),
+            None => Next::Continue,
+        }
+    }
+}
+
+///Invocations of the callback, used to release replaced handlers.
+struct Calls {
+    running: usize,
+    //Threads, which currently run callback, to not wait for themselves
+    threads: Vec<usize>,
+    //Replaced handlers, which might be still used by running callbacks
+    retired: Vec<*mut Handler>,
+}
+
+//Number of independently locked parts of registry, so that expirations of different timers rarely contend.
+const SHARDS: usize = 16;
+//Lower half of context is index of slot, while upper half is its generation.
+const INDEX_BITS: u32 = usize::BITS / 2;
+const INDEX_MASK: usize = (1 << INDEX_BITS) - 1;
+const NIL: usize = usize::MAX;
+
+struct Slot {
+    //Pointer to state, zero if slot is vacant
+    state: usize,
+    generation: usize,
+    //Next vacant slot
+    next: usize,
+}
+
+///Timers by context, passed to the OS timer.
+///
+///OS can deliver expiration after timer is deleted, so callback looks up its state instead of referencing it directly.
+///Slots are reused, so context includes slot's generation, to ignore expirations of deleted timers.
+struct Registry {
+    slots: Vec<Slot>,
+    vacant: usize,
+}
+
+static REGISTRY: [Lock<Registry>; SHARDS] = [const { Lock::new(Registry::new()) }; SHARDS];
+static NEXT_SHARD: AtomicUsize = AtomicUsize::new(0);
+
+impl Registry {
+    const fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            vacant: NIL,
+        }
+    }
+
+    ///Returns shard, index and generation of slot, identified by `context`.
+    const fn locate(context: usize) -> (usize, usize, usize) {
+        let index = context & INDEX_MASK;
+        (index % SHARDS, index / SHARDS, context >> INDEX_BITS)
+    }
+
+    ///Registers `state`, returning its context, or zero if there are no free slots.
+    ///
+    ///Memory is never allocated under lock, so expirations don't wait for allocator.
+    fn insert(state: usize) -> usize {
+        let shard = NEXT_SHARD.fetch_add(1, Ordering::Relaxed) % SHARDS;
+        let mut spare = Vec::new();
+
+        loop {
+            let mut registry = REGISTRY[shard].lock();
+            let index = match registry.vacant {
+                NIL => {
+                    let index = registry.slots.len();
+                    if index * SHARDS + shard > INDEX_MASK {
+                        return 0;
+                    }
+
+                    if index == registry.slots.capacity() {
+                        if spare.capacity() <= index {
+                            let capacity = core::cmp::max(index * 2, 8);
+                            drop(registry);
+                            spare = Vec::with_capacity(capacity);
+                            continue;
+                        }
+
+                        //Old storage is released after lock
+                        spare.append(&mut registry.slots);
+                        mem::swap(&mut registry.slots, &mut spare);
+                    }
+
+                    registry.slots.push(Slot {
+                        state,
+                        generation: 1,
+                        next: NIL,
+                    });
+                    index
+                },
+                index => {
+                    let slot = &mut registry.slots[index];
+                    slot.state = state;
+                    registry.vacant = mem::replace(&mut slot.next, NIL);
+                    index
+                },
+            };
+
+            return (registry.slots[index].generation << INDEX_BITS) | (index * SHARDS + shard);
+        }
+    }
+
+    ///Frees slot with `index`, so that its context is no longer valid.
+    fn remove(&mut self, index: usize) {
+        let slot = &mut self.slots[index];
+        slot.state = 0;
+        slot.generation = match (slot.generation + 1) & (usize::MAX >> INDEX_BITS) {
+            0 => 1,
+            generation => generation,
+        };
+        slot.next = self.vacant;
+        self.vacant = index;
+    }
+}
+
+///Expirations being processed, shared with them to outlive timer's state.
+struct Firing {
+    count: AtomicUsize,
+    //Threads, waiting for expirations to be processed
+    #[cfg(feature = "std")]
+    waiters: Lock<Vec<std::thread::Thread>>,
+}
+
+impl Firing {
+    ///Waits until no expiration is being processed.
+    ///
+    ///Without `std` feature it spins, occupying CPU until callback returns.
+    fn wait(&self) {
+        while self.count.load(Ordering::SeqCst) != 0 {
+            #[cfg(feature = "std")]
+            {
+                self.waiters.lock().push(std::thread::current());
+                //Expiration might finish before registration
+                if self.count.load(Ordering::SeqCst) == 0 {
+                    break;
+                }
+                std::thread::park();
+            }
+            #[cfg(not(feature = "std"))]
+            core::hint::spin_loop();
+        }
+    }
+
+    fn finish(&self) {
+        if self.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            #[cfg(feature = "std")]
+            for waiter in mem::take(&mut *self.waiters.lock()) {
+                waiter.unpark();
+            }
+        }
+    }
+}
+
+///Timer's state, passed to the OS timer as callback's context.
+pub(crate) struct State {
+    handle: AtomicUsize,
+    //Key in `REGISTRY`, zero if timer is not dispatched by crate
+    context: usize,
+    handler: AtomicPtr<Handler>,
+    calls: Lock<Calls>,
+    plan: Lock<Plan>,
+    //Owned by `Timer`, its handles and every running callback, last one deletes timer
+    refs: AtomicUsize,
+    //Set once timer is closed, after which callback is never invoked
+    closed: AtomicBool,
+    firing: Arc<Firing>,
+}
+
+impl State {
+    fn alloc(cb: Callback, handle: usize) -> *mut Self {
+        let handler = Box::into_raw(Box::new(Handler::new(cb)));
+
+        Box::into_raw(Box::new(Self {
+            handle: AtomicUsize::new(handle),
+            context: 0,
+            handler: AtomicPtr::new(handler),
+            calls: Lock::new(Calls {
+                running: 0,
+                threads: Vec::new(),
+                retired: Vec::new(),
+            }),
+            plan: Lock::new(Plan::new(0)),
+            refs: AtomicUsize::new(1),
+            closed: AtomicBool::new(false),
+            firing: Arc::new(Firing {
+                count: AtomicUsize::new(0),
+                #[cfg(feature = "std")]
+                waiters: Lock::new(Vec::new()),
+            }),
+        }))
+    }
+
+    fn create(cb: Callback) -> *mut Self {
+        let state = Self::alloc(cb, 0);
+
+        let handle = unsafe {
+            //OS is not aware of context yet, so nothing else accesses state
+            (*state).context = Registry::insert(state as usize);
+            match (*state).context {
+                0 => 0,
+                context => sys::BACKEND.create(TimerContext::new(context)),
+            }
+        };
+
+        if handle == 0 {
+            unsafe {
+                Self::release(state);
+            }
+            ptr::null_mut()
+        } else {
+            //Safe to set, as timer cannot fire before being armed.
+            unsafe {
+                (*state).handle.store(handle, Ordering::Release);
+            }
+            state
+        }
+    }
+
+    #[inline(always)]
+    fn handle(&self) -> usize {
+        self.handle.load(Ordering::Acquire)
+    }
+
+    fn arm(&self, timeout: time::Duration, interval: time::Duration, options: Options) -> bool {
+        let mut plan = self.plan.lock();
+        //Owner is gone, only handles are left
+        if self.closed.load(Ordering::Acquire) {
+            return false;
+        }
+
+        //Adopted timer doesn't notify state, so it cannot follow plan
+        if self.context == 0 && (options.times.is_some() || options.until.is_some() || options.fixed_delay || options.jitter.is_some()) {
+            return false;
+        }
+
+        *plan = Plan::new(plan.epoch.wrapping_add(1));
+        plan.fires_left = options.times;
+        plan.until = options.until;
+        plan.fixed_delay = options.fixed_delay;
+        plan.jitter = options.jitter;
+        plan.leeway = options.leeway;
+
+        let now = monotonic_now();
+        //Fixed delay and jitter are implemented via one shot timer, re-armed on each alarm.
+        if interval != time::Duration::ZERO && (options.fixed_delay || options.jitter.is_some()) {
+            plan.period = Some(interval);
+            plan.nominal = now + timeout;
+            plan.rearm(self.handle(), now, timeout);
+            return true;
+        }
+
+        let timeout = match options.jitter {
+            Some(jitter) => timeout.saturating_add(jitter.offset(timeout)),
+            None => timeout,
+        };
+        plan.armed = Some((now + timeout, interval));
+        unsafe {
+            sys::BACKEND.set(self.handle(), timeout, interval, plan.leeway)
+        }
+    }
+
+    ///Cancels timer, waiting until none of its callbacks is running.
+    ///
+    ///If called from within callback, it doesn't wait, as callback cannot wait for itself to finish.
+    pub(crate) fn cancel(&self) {
+        {
+            let mut plan = self.plan.lock();
+            *plan = Plan::new(plan.epoch.wrapping_add(1));
+            unsafe {
+                sys::BACKEND.disarm(self.handle());
+            }
+        }
+
+        let thread = sys::BACKEND.thread_id();
+        if self.calls.lock().threads.contains(&thread) {
+            return;
+        }
+
+        unsafe {
+            sys::BACKEND.wait(self.handle());
+        }
+        //Expiration, delivered right before cancel, is either still running or skips callback.
+        self.firing.wait();
+    }
+
+    fn is_armed(&self) -> bool {
+        unsafe {
+            sys::BACKEND.get(self.handle())
+        }
+    }
+
+    ///Returns remaining time until next alarm and interval, according to schedule.
+    fn planned(plan: &Plan) -> Option<(time::Duration, time::Duration)> {
+        match plan.armed {
+            Some((deadline, interval)) => remaining_until(deadline, interval, monotonic_now()).map(|remaining| (remaining, interval)),
+            None => None,
+        }
+    }
+
+    fn remaining(&self) -> Option<time::Duration> {
+        let plan = self.plan.lock();
+        if !self.is_armed() {
+            return None;
+        }
+
+        //OS reports exact time, even if timer has been armed elsewhere
+        match unsafe { sys::remaining(self.handle()) } {
+            Some((remaining, _)) => Some(remaining),
+            None => Self::planned(&plan).map(|(remaining, _)| remaining),
+        }
+    }
+
+    pub(crate) fn pause(&self) -> bool {
+        let mut plan = self.plan.lock();
+
+        if !self.is_armed() {
+            return false;
+        }
+
+        let planned = Self::planned(&plan);
+        //Timer, armed elsewhere, can only be paused if OS reports its remaining time
+        if planned.is_none() && unsafe { sys::remaining(self.handle()) }.is_none() {
+            return false;
+        }
+
+        let remaining = match unsafe { sys::pause(self.handle()) } {
+            Some(remaining) => remaining,
+            None => match planned {
+                Some(remaining) => remaining,
+                None => return false,
+            },
+        };
+
+        plan.epoch = plan.epoch.wrapping_add(1);
+        plan.armed = None;
+        plan.paused = Some(remaining);
+        true
+    }
+
+    pub(crate) fn resume(&self) -> bool {
+        let mut plan = self.plan.lock();
+
+        match plan.paused.take() {
+            Some((timeout, interval)) => {
+                let now = monotonic_now();
+                //Re-armed schedule keeps its rate relative to resumed alarm
+                plan.nominal = now + timeout;
+                plan.armed = Some((now + timeout, interval));
+                unsafe {
+                    sys::BACKEND.set(self.handle(), timeout, interval, plan.leeway)
+                }
+            },
+            None => false,
+        }
+    }
+
+    ///Cancels timer for good, waiting until none of its callbacks is running.
+    ///
+    ///If called from within callback, it doesn't wait for it to finish.
+    pub(crate) fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.cancel();
+    }
+
+    ///Releases reference to the state, deleting timer once it is the last one.
+    unsafe fn release(state: *mut Self) {
+        match (*state).context {
+            0 => if (*state).refs.fetch_sub(1, Ordering::AcqRel) != 1 {
+                return;
+            },
+            context => {
+                //Expiration takes reference under the same lock, so it either finds state alive or doesn't find it at all.
+                let (shard, index, _) = Registry::locate(context);
+                let mut registry = REGISTRY[shard].lock();
+                if (*state).refs.fetch_sub(1, Ordering::AcqRel) != 1 {
+                    return;
+                }
+                registry.remove(index);
+            },
+        }
+
+        let handle = (*state).handle();
+        if handle != 0 {
+            sys::BACKEND.delete(handle);
+        }
+        drop(Box::from_raw(state));
+    }
+
+    ///Invoked by backend on timer expiration, with function to call user's callback.
+    ///
+    ///Keeps state alive until callback returns, even if `Timer` is dropped by callback itself.
+    ///Does nothing if timer has been already deleted.
+    pub(crate) unsafe fn on_fire<F: FnOnce(sys::ffi::Callback, *mut sys::ffi::c_void)>(context: usize, invoke: F) {
+        let (state, firing) = {
+            let (shard, index, generation) = Registry::locate(context);
+            let registry = REGISTRY[shard].lock();
+            match registry.slots.get(index) {
+                Some(slot) if slot.state != 0 && slot.generation == generation => {
+                    let state = &*(slot.state as *const Self);
+                    state.refs.fetch_add(1, Ordering::AcqRel);
+                    state.firing.count.fetch_add(1, Ordering::SeqCst);
+                    (state as *const Self, state.firing.clone())
+                },
+                _ => return,
+            }
+        };
+
+        if !(*state).closed.load(Ordering::SeqCst) {
+            (*state).fire(invoke);
+        }
+        //Waiting owner is woken up only after reference is released, so that it releases state itself
+        Self::release(state as *mut Self);
+        firing.finish();
+    }
+
+    fn fire<F: FnOnce(sys::ffi::Callback, *mut sys::ffi::c_void)>(&self, invoke: F) {
+        let epoch = {
+            let mut plan = self.plan.lock();
+            //Expiration, delivered after timer has been cancelled or paused
+            if plan.armed.is_none() {
+                return;
+            }
+            let now = monotonic_now();
+
+            //Deadline of next alarm, excluding jitter
+            let next = match plan.period {
+                Some(period) => {
+                    //One shot has been expired
+                    plan.armed = None;
+                    match plan.fixed_delay {
+                        true => Some(now + period),
+                        false => remaining_until(plan.nominal, period, now).map(|remaining| now + remaining),
+                    }
+                },
+                None => match plan.armed {
+                    Some((deadline, interval)) => remaining_until(deadline, interval, now).map(|remaining| now + remaining),
+                    None => None,
+                },
+            };
+
+            let mut is_last = false;
+
+            if let Some(until) = plan.until {
+                if now >= until {
+                    plan.armed = None;
+                    unsafe {
+                        sys::BACKEND.disarm(self.handle());
+                    }
+                    return;
+                }
+
+                //Stop right away if next expiration is past the deadline.
+                is_last = match next {
+                    Some(next) => next > until,
+                    None => true,
+                };
+            }
+
+            match plan.fires_left {
+                Some(0) => {
+                    plan.armed = None;
+                    unsafe {
+                        sys::BACKEND.disarm(self.handle());
+                    }
+                    return;
+                },
+                Some(1) => is_last = true,
+                Some(left) => plan.fires_left = Some(left - 1),
+                None => (),
+            }
+
+            if is_last {
+                plan.fires_left = Some(0);
+                plan.armed = None;
+                unsafe {
+                    sys::BACKEND.disarm(self.handle());
+                }
+            } else if let (Some(next), false) = (next, plan.fixed_delay) {
+                if plan.period.is_some() {
+                    //Fixed-rate is re-armed right away, to not depend on callback's duration.
+                    plan.nominal = next;
+                    plan.rearm(self.handle(), now, next - now);
+                }
+            }
+
+            sys::apply_leeway(plan.leeway);
+            plan.epoch
+        };
+
+        let thread = sys::BACKEND.thread_id();
+        let handler = {
+            let mut calls = self.calls.lock();
+            calls.running += 1;
+            calls.threads.push(thread);
+            self.handler.load(Ordering::Acquire)
+        };
+
+        let next = unsafe {
+            (*handler).call(invoke)
+        };
+
+        let retired = {
+            let mut calls = self.calls.lock();
+            calls.running -= 1;
+            if let Some(idx) = calls.threads.iter().position(|running| *running == thread) {
+                calls.threads.swap_remove(idx);
+            }
+            match calls.running {
+                0 => mem::take(&mut calls.retired),
+                _ => Vec::new(),
+            }
+        };
+        for handler in retired {
+            unsafe {
+                drop(Box::from_raw(handler));
+            }
+        }
+
+        let mut plan = self.plan.lock();
+        //Apply only if schedule has not been changed by callback
+        if plan.epoch != epoch {
+            return;
+        }
+
+        match next {
+            Next::Continue => if let (Some(period), true) = (plan.period, plan.fixed_delay) {
+                if plan.fires_left != Some(0) {
+                    plan.rearm(self.handle(), monotonic_now(), period);
+                }
+            },
+            Next::Stop => {
+                //Cannot wait for callbacks, as it is invoked from one.
+                *plan = Plan::new(epoch.wrapping_add(1));
+                unsafe {
+                    sys::BACKEND.disarm(self.handle());
+                }
+            },
+            Next::RescheduleIn(timeout) => if plan.fires_left != Some(0) {
+                let timeout = core::cmp::max(timeout, MIN_TIMEOUT);
+                let now = monotonic_now();
+                match plan.period {
+                    Some(_) => {
+                        plan.nominal = now + timeout;
+                        plan.rearm(self.handle(), now, timeout);
+                    },
+                    None => {
+                        let interval = match plan.armed {
+                            Some((_, interval)) => interval,
+                            None => time::Duration::ZERO,
+                        };
+                        plan.armed = Some((now + timeout, interval));
+                        unsafe {
+                            sys::BACKEND.set(self.handle(), timeout, interval, plan.leeway);
+                        }
+                    },
+                }
+            },
+        }
+    }
+}
+
+///OS timer wrapper
+///
+///Timer can be cancelled, re-scheduled or dropped from within its own callback.
+pub struct Timer {
+    inner: AtomicPtr<State>,
+}
 
 unsafe impl Send for Timer {}
 unsafe impl Sync for Timer {}
 
 impl Timer {
+    #[inline]
+    ///Creates new uninitialized instance.
+    ///
+    ///In order to use it one must call `init`.
+    pub const unsafe fn uninit() -> Self {
+        Self {
+            inner: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    #[inline(always)]
+    fn get_inner(&self) -> &State {
+        let inner = self.inner.load(Ordering::Acquire);
+        assert!(!inner.is_null(), "Timer has not been initialized");
+        unsafe {
+            &*inner
+        }
+    }
+
+    #[inline(always)]
+    ///Returns whether timer is initialized
+    pub fn is_init(&self) -> bool {
+        !self.inner.load(Ordering::Acquire).is_null()
+    }
+
+    #[must_use]
+    ///Performs timer initialization
+    ///
+    ///`cb` is variant of callback to invoke when timer expires
+    ///
+    ///Returns whether timer has been initialized successfully or not.
+    ///
+    ///If timer is already initialized does nothing, returning false.
+    pub fn init(&self, cb: Callback) -> bool {
+        if self.is_init() {
+            return false;
+        }
+
+        let state = State::create(cb);
+        if state.is_null() {
+            return false;
+        }
+
+        match self.inner.compare_exchange(ptr::null_mut(), state, Ordering::SeqCst, Ordering::Acquire) {
+            Ok(_) => true,
+            Err(_) => {
+                unsafe {
+                    State::release(state);
+                }
+                false
+            }
+        }
+    }
+
+    ///Creates new timer, invoking provided `cb` when timer expires.
+    ///
+    ///On failure, returns `None`
+    pub fn new(cb: Callback) -> Option<Self> {
+        let state = State::create(cb);
+        if state.is_null() {
+            return None;
+        }
+
+        Some(Self {
+            inner: AtomicPtr::new(state),
+        })
+    }
+
     #[inline(always)]
     ///Creates new schedule
     pub const fn schedule(&self) -> Schedule<'_> {
         Schedule {
-            timer: self,
+            target: Target::Timer(self),
             timeout: time::Duration::from_millis(0),
             interval: time::Duration::from_secs(0),
+            options: Options::new(),
         }
     }
 
-    #[cfg(not(any(target_os = "macos", target_os = "ios")))]
     #[inline(always)]
     ///Schedules timer to alarm once after `timeout` passes.
     ///
@@ -67,15 +1045,320 @@ impl Timer {
         assert!(!(timeout.as_secs() == 0 && timeout.as_nanos() == 0), "Cannot set zero initial timeout");
         self.schedule_interval(timeout, time::Duration::from_secs(0))
     }
+
+    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`.
+    ///
+    ///Note that if timer has been scheduled before, but hasn't expire yet, behaviour is undefined (Callback may or may not be called).
+    ///To prevent that user must `cancel` timer first.
+    ///
+    ///# Note
+    ///
+    ///- On Windows `interval` is truncated to milliseconds and by `u32::max_value()`
+    ///- On Mac `timeout` is truncated by `i64::max_value()` and `interval` by `u64::max_value()`
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_interval(&self, timeout: time::Duration, interval: time::Duration) -> bool {
+        self.get_inner().arm(timeout, interval, Options::new())
+    }
+
+    #[inline]
+    ///Returns `true` if timer has been scheduled and still pending.
+    ///
+    ///On Win/Mac it only returns whether timer has been scheduled, as there is no way to check
+    ///whether timer is ongoing
+    pub fn is_scheduled(&self) -> bool {
+        self.get_inner().is_armed()
+    }
+
+    #[inline]
+    ///Returns time remaining until next alarm, if timer is scheduled.
+    pub fn remaining(&self) -> Option<time::Duration> {
+        self.get_inner().remaining()
+    }
+
+    #[inline]
+    ///Cancels ongoing timer, if it was scheduled, waiting for running callbacks to finish.
+    ///
+    ///Paused timer is cancelled too, discarding remaining time.
+    ///
+    ///Can be called from within timer's own callback, in which case it doesn't wait for callbacks to finish.
+    ///
+    ///Without `std` feature it spins while waiting, otherwise thread is parked until callbacks finish.
+    pub fn cancel(&self) {
+        self.get_inner().cancel()
+    }
+
+    ///Pauses ongoing timer, preserving remaining time until next alarm and interval.
+    ///
+    ///Returns `true` if timer was scheduled and now paused.
+    pub fn pause(&self) -> bool {
+        self.get_inner().pause()
+    }
+
+    ///Resumes paused timer, scheduling it with remaining time and original interval.
+    ///
+    ///Returns `true` if timer was paused and successfully scheduled again.
+    pub fn resume(&self) -> bool {
+        self.get_inner().resume()
+    }
+
+    #[inline(always)]
+    ///Cancels timer for good, waiting until none of its callbacks is running.
+    pub(crate) fn close(&self) {
+        self.get_inner().close()
+    }
+
+    #[must_use]
+    ///Performs initialization of timer, which is part of `owner`, to invoke `cb` with `owner`, while it is alive.
+    ///
+    ///Callback keeps `owner` alive until it returns, even if `owner` is dropped by callback itself.
+    pub(crate) fn init_owned<T: Send + Sync + 'static, F: FnMut(&T) + Send + 'static>(&self, owner: &Arc<T>, mut cb: F) -> bool {
+        let owner = Arc::downgrade(owner);
+        self.init(Callback::closure(move || {
+            if let Some(owner) = owner.upgrade() {
+                cb(&owner);
+            }
+        }))
+    }
+
+    ///Replaces timer's callback, preserving its schedule.
+    ///
+    ///Old callback is dropped once no invocation of it is running.
+    pub fn set_callback(&self, cb: Callback) {
+        let state = self.get_inner();
+        let handler = Box::into_raw(Box::new(Handler::new(cb)));
+
+        let old = {
+            let mut calls = state.calls.lock();
+            let old = state.handler.swap(handler, Ordering::AcqRel);
+            match calls.running {
+                0 => Some(old),
+                _ => {
+                    calls.retired.push(old);
+                    None
+                },
+            }
+        };
+
+        if let Some(old) = old {
+            unsafe {
+                drop(Box::from_raw(old));
+            }
+        }
+    }
+
+    ///Creates handle, which allows to control timer from any thread.
+    pub fn handle(&self) -> TimerHandle {
+        let state = self.get_inner();
+        state.refs.fetch_add(1, Ordering::AcqRel);
+        TimerHandle {
+            state,
+        }
+    }
+
+    #[inline]
+    ///Returns underlying OS timer.
+    ///
+    ///It is `timer_t` on POSIX, `PTP_TIMER` on Windows and `dispatch_source_t` on Mac.
+    pub fn as_raw(&self) -> *mut sys::ffi::c_void {
+        unsafe {
+            sys::as_raw(self.get_inner().handle())
+        }
+    }
+
+    ///Releases ownership of timer, returning underlying OS timer and context of its callback.
+    ///
+    ///Timer remains scheduled, but it is leaked unless restored with `from_raw_parts`.
+    pub fn into_raw_parts(self) -> (*mut sys::ffi::c_void, *mut sys::ffi::c_void) {
+        let state = self.get_inner();
+        let parts = (self.as_raw(), state as *const State as *mut sys::ffi::c_void);
+        mem::forget(self);
+        parts
+    }
+
+    ///Restores timer from parts, returned by `into_raw_parts`.
+    ///
+    ///If `context` is null, then takes ownership of OS timer created elsewhere (e.g. by C library).
+    ///Such timer can be scheduled and queried, but its notification remains as it was created.
+    ///Hence scheduling it with `times`, `until`, `fixed_delay` or `jitter` fails,
+    ///and callback, such as `Callback::controlled`, cannot be attached to it.
+    ///On Mac it must be suspended dispatch source.
+    ///
+    ///On Windows and Mac remaining time of timer, armed before adoption, is unknown,
+    ///so it cannot be paused until scheduled again.
+    ///
+    ///# Safety
+    ///
+    ///`raw` must be valid OS timer, not owned by anything else.
+    ///Non-null `context` must be returned by `into_raw_parts` together with `raw`.
+    pub unsafe fn from_raw_parts(raw: *mut sys::ffi::c_void, context: *mut sys::ffi::c_void) -> Self {
+        let state = match context.is_null() {
+            true => State::alloc(Callback::raw(sys::timer_callback, ptr::null_mut()), sys::adopt(raw)),
+            false => {
+                let state = context as *mut State;
+                debug_assert_eq!(raw, sys::as_raw((*state).handle()));
+                state
+            },
+        };
+
+        Self {
+            inner: AtomicPtr::new(state),
+        }
+    }
+
+    #[inline]
+    ///Returns whether timer is paused.
+    pub fn is_paused(&self) -> bool {
+        self.get_inner().plan.lock().paused.is_some()
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        let state = *self.inner.get_mut();
+        if !state.is_null() {
+            //Once dropped, callback is never invoked, and running ones are waited for, unless dropped within callback.
+            //In latter case timer is deleted once callback returns.
+            unsafe {
+                (*state).close();
+                State::release(state);
+            }
+        }
+    }
+}
+
+impl Drop for State {
+    fn drop(&mut self) {
+        unsafe {
+            drop(Box::from_raw(*self.handler.get_mut()));
+        }
+        for handler in self.calls.lock().retired.drain(..) {
+            unsafe {
+                drop(Box::from_raw(handler));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///Error, indicating that `Timer` has been dropped.
+pub struct Gone;
+
+impl core::fmt::Display for Gone {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        fmt.write_str("Timer has been dropped")
+    }
+}
+
+///Handle to the `Timer`, which can be shared between threads.
+///
+///Keeps timer's state alive, but its methods fail with `Gone` once `Timer` is dropped.
+pub struct TimerHandle {
+    state: *const State,
+}
+
+unsafe impl Send for TimerHandle {}
+unsafe impl Sync for TimerHandle {}
+
+impl TimerHandle {
+    #[inline(always)]
+    ///Returns state, even if `Timer` is dropped.
+    pub(crate) fn state(&self) -> &State {
+        unsafe {
+            &*self.state
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn get_inner(&self) -> Result<&State, Gone> {
+        let state = self.state();
+
+        match state.closed.load(Ordering::Acquire) {
+            true => Err(Gone),
+            false => Ok(state),
+        }
+    }
+
+    #[inline]
+    ///Creates new schedule
+    ///
+    ///If `Timer` is dropped before schedule is applied, it does nothing, returning `false`
+    pub fn schedule(&self) -> Result<Schedule<'_>, Gone> {
+        let state = self.get_inner()?;
+        Ok(Schedule {
+            target: Target::Handle(state),
+            timeout: time::Duration::from_millis(0),
+            interval: time::Duration::from_secs(0),
+            options: Options::new(),
+        })
+    }
+
+    #[inline]
+    ///Cancels ongoing timer, if it was scheduled, waiting for running callbacks to finish.
+    pub fn cancel(&self) -> Result<(), Gone> {
+        self.get_inner()?.cancel();
+        Ok(())
+    }
+
+    #[inline]
+    ///Returns `true` if timer has been scheduled and still pending.
+    pub fn is_scheduled(&self) -> Result<bool, Gone> {
+        Ok(self.get_inner()?.is_armed())
+    }
+
+    #[inline]
+    ///Returns time remaining until next alarm, if timer is scheduled.
+    pub fn remaining(&self) -> Result<Option<time::Duration>, Gone> {
+        Ok(self.get_inner()?.remaining())
+    }
+}
+
+impl Clone for TimerHandle {
+    #[inline]
+    fn clone(&self) -> Self {
+        unsafe {
+            (*self.state).refs.fetch_add(1, Ordering::AcqRel);
+        }
+        Self {
+            state: self.state,
+        }
+    }
+}
+
+impl Drop for TimerHandle {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            State::release(self.state as *mut State);
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Target<'a> {
+    Timer(&'a Timer),
+    Handle(&'a State),
+}
+
+impl<'a> Target<'a> {
+    #[inline(always)]
+    fn state(&self) -> &'a State {
+        match self {
+            Target::Timer(timer) => timer.get_inner(),
+            Target::Handle(state) => state,
+        }
+    }
 }
 
 ///Timer's schedule
 ///
 ///If initial timeout is not configured, then it is set to `interval` timeout
 pub struct Schedule<'a> {
-    timer: &'a Timer,
+    target: Target<'a>,
     timeout: time::Duration,
     interval: time::Duration,
+    options: Options,
 }
 
 impl<'a> Schedule<'a> {
@@ -98,15 +1381,137 @@ impl<'a> Schedule<'a> {
         self
     }
 
+    #[inline(always)]
+    ///Limits number of times timer fires, after which it is cancelled.
+    pub const fn times(mut self, times: u32) -> Self {
+        assert!(times > 0, "Cannot limit timer to zero fires");
+        self.options.times = Some(times);
+        self
+    }
+
+    #[cfg(feature = "std")]
+    #[inline]
+    ///Sets `deadline` after which timer no longer fires and is cancelled.
+    pub fn until(mut self, deadline: std::time::Instant) -> Self {
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        self.options.until = Some(monotonic_now() + remaining);
+        self
+    }
+
+    #[inline(always)]
+    ///Switches periodic schedule from fixed-rate to fixed-delay.
+    ///
+    ///By default timer fires every `interval` regardless of how long callback runs (fixed-rate).
+    ///With fixed-delay next alarm is only armed after callback returns, to fire after `interval`,
+    ///guaranteeing spacing between callback invocations.
+    ///
+    ///Timer is not considered scheduled while callback runs.
+    pub const fn fixed_delay(mut self) -> Self {
+        self.options.fixed_delay = true;
+        self
+    }
+
+    #[inline(always)]
+    ///Adds random offset within `[0, max]` to each alarm.
+    ///
+    ///Spreads alarms of timers, that were scheduled at the same time, such as across process restarts.
+    ///Periodic timer is re-armed on each alarm, without accumulating offsets.
+    pub const fn jitter(mut self, max: time::Duration) -> Self {
+        self.options.jitter = Some(Spread::Uniform(max));
+        self
+    }
+
+    #[inline(always)]
+    ///Adds random offset within `[0, ratio * timeout]` to each alarm.
+    ///
+    ///Proportional variant of `jitter`, where `timeout` is time until alarm.
+    pub fn jitter_ratio(mut self, ratio: f64) -> Self {
+        assert!(ratio.is_finite() && ratio >= 0.0, "Jitter ratio must be finite and non-negative");
+        self.options.jitter = Some(Spread::Proportional(ratio));
+        self
+    }
+
+    #[inline(always)]
+    ///Allows OS to defer each alarm by up to `leeway`, so that it can be coalesced with other wake ups.
+    ///
+    ///# Note
+    ///
+    ///- On Linux alarm itself is never deferred, as POSIX timers ignore timer slack.
+    ///Instead `leeway` is applied as timer slack of callback's thread, affecting only sleeps and waits made inside callback.
+    ///- Other POSIX systems ignore it.
+    ///- On Windows it is truncated to milliseconds.
+    pub const fn leeway(mut self, leeway: time::Duration) -> Self {
+        self.options.leeway = leeway;
+        self
+    }
+
     #[inline(always)]
     ///Schedules timer execution, using provided settings.
     ///
     ///Returns `true` if successfully set, otherwise on error returns `false`
     pub fn schedule(&self) -> bool {
+        let state = self.target.state();
+
         if self.timeout == time::Duration::ZERO {
-            self.timer.schedule_interval(self.interval, self.interval)
+            state.arm(self.interval, self.interval, self.options)
         } else {
-            self.timer.schedule_interval(self.timeout, self.interval)
+            state.arm(self.timeout, self.interval, self.options)
+        }
+    }
+
+    #[inline]
+    ///Schedules timer execution, returning guard which cancels timer when dropped.
+    ///
+    ///Returns `None` if failed to set.
+    pub fn schedule_guarded(&self) -> Option<ScheduleGuard<'a>> {
+        match self.schedule() {
+            true => Some(ScheduleGuard {
+                target: self.target,
+            }),
+            false => None,
+        }
+    }
+}
+
+#[must_use = "Timer is cancelled as soon as guard is dropped"]
+///Guard, cancelling timer on drop.
+///
+///Created with `Schedule::schedule_guarded`
+pub struct ScheduleGuard<'a> {
+    target: Target<'a>,
+}
+
+impl ScheduleGuard<'_> {
+    #[inline]
+    ///Returns `true` if timer is still pending.
+    pub fn is_scheduled(&self) -> bool {
+        self.target.state().is_armed()
+    }
+
+    #[inline]
+    ///Releases guard, leaving timer scheduled.
+    pub fn dismiss(self) {
+        mem::forget(self);
+    }
+}
+
+impl Drop for ScheduleGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.target.state().cancel();
+    }
+}
+
+///Calculates time remaining until next alarm of timer, which first fires at `deadline` and then every `interval`.
+///
+///Returns `None` if timer is one shot and it already expired.
+fn remaining_until(deadline: time::Duration, interval: time::Duration, now: time::Duration) -> Option<time::Duration> {
+    match deadline.checked_sub(now) {
+        Some(remaining) if remaining != time::Duration::ZERO => Some(remaining),
+        _ if interval == time::Duration::ZERO => None,
+        _ => {
+            let passed = (now - deadline).as_nanos() % interval.as_nanos();
+            Some(interval - time::Duration::from_nanos(passed as u64))
         }
     }
 }
@@ -130,8 +1535,218 @@ impl Drop for BoxFnPtr {
     fn drop(&mut self) {
         if !self.is_null() {
             unsafe {
-                let _ = Box::from_raw(mem::transmute::<_, *mut dyn FnMut()>(self.0));
+                let _ = Box::from_raw(mem::transmute::<FatPtr, *mut dyn FnMut()>(self.0));
             }
         }
     }
 }
+
+//Tests rely on backend to create timers
+#[cfg(all(test, not(os_timer_backend = "custom")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn init_plain_fn() {
+        let mut timer = unsafe {
+            Timer::uninit()
+        };
+
+        fn cb() {
+        }
+
+        let closure = || {
+        };
+
+        assert!(timer.init(Callback::plain(cb)));
+        let ptr = *timer.inner.get_mut();
+        assert!(!ptr.is_null());
+        assert!(unsafe { &*timer.get_inner().handler.load(Ordering::Relaxed) }.data.is_null());
+
+        assert!(!timer.init(Callback::closure(closure)));
+        assert!(!ptr.is_null());
+        assert_eq!(ptr, timer.inner.load(Ordering::Relaxed));
+        assert!(unsafe { &*timer.get_inner().handler.load(Ordering::Relaxed) }.data.is_null());
+    }
+
+    #[test]
+    fn init_closure() {
+        let mut timer = unsafe {
+            Timer::uninit()
+        };
+
+        fn cb() {
+        }
+
+        let closure = || {
+        };
+
+        assert!(timer.init(Callback::closure(closure)));
+        let ptr = *timer.inner.get_mut();
+        assert!(!ptr.is_null());
+        assert!(!unsafe { &*timer.get_inner().handler.load(Ordering::Relaxed) }.data.is_null());
+
+        assert!(!timer.init(Callback::plain(cb)));
+        assert!(!ptr.is_null());
+        assert_eq!(ptr, timer.inner.load(Ordering::Relaxed));
+        assert!(!unsafe { &*timer.get_inner().handler.load(Ordering::Relaxed) }.data.is_null());
+    }
+
+    #[cfg(all(target_os = "linux", os_timer_backend = "posix"))]
+    #[test]
+    fn leeway_sets_callback_slack() {
+        use core::sync::atomic::AtomicUsize;
+
+        static SLACK: AtomicUsize = AtomicUsize::new(0);
+
+        fn cb() {
+            let slack = unsafe {
+                libc::prctl(libc::PR_GET_TIMERSLACK)
+            };
+            SLACK.store(slack as usize, Ordering::SeqCst);
+        }
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        assert!(timer.schedule().initial(time::Duration::from_millis(10)).leeway(time::Duration::from_millis(5)).schedule());
+
+        for _ in 0..100 {
+            if SLACK.load(Ordering::SeqCst) != 0 {
+                break;
+            }
+            unsafe {
+                libc::usleep(10_000);
+            }
+        }
+        assert_eq!(SLACK.load(Ordering::SeqCst), 5_000_000);
+    }
+
+    #[cfg(all(target_os = "linux", os_timer_backend = "posix"))]
+    #[test]
+    fn adopt_foreign_timer() {
+        let mut raw = mem::MaybeUninit::<libc::timer_t>::uninit();
+        let mut event: libc::sigevent = unsafe {
+            mem::zeroed()
+        };
+        //Ignored by default, in case it fires
+        event.sigev_notify = libc::SIGEV_SIGNAL;
+        event.sigev_signo = libc::SIGWINCH;
+        assert_eq!(unsafe { libc::timer_create(libc::CLOCK_MONOTONIC, &mut event, raw.as_mut_ptr()) }, 0);
+        let raw = unsafe {
+            raw.assume_init()
+        };
+
+        let timer = unsafe {
+            Timer::from_raw_parts(raw as _, ptr::null_mut())
+        };
+        assert_eq!(timer.as_raw(), raw as _);
+        assert!(!timer.is_scheduled());
+
+        assert!(timer.schedule_once(time::Duration::from_secs(10)));
+        assert!(timer.is_scheduled());
+        let remaining = timer.remaining().expect("To have remaining time");
+        assert!(remaining <= time::Duration::from_secs(10));
+
+        timer.cancel();
+        assert!(!timer.is_scheduled());
+
+        //Options, relying on notification, are rejected
+        assert!(!timer.schedule().initial(time::Duration::from_secs(10)).times(2).schedule());
+        assert!(!timer.is_scheduled());
+
+        //Armed by foreign code, bypassing schedule
+        let value = libc::itimerspec {
+            it_interval: libc::timespec {
+                tv_sec: 5,
+                tv_nsec: 0,
+            },
+            it_value: libc::timespec {
+                tv_sec: 20,
+                tv_nsec: 0,
+            },
+        };
+        assert_eq!(unsafe { libc::timer_settime(raw, 0, &value, ptr::null_mut()) }, 0);
+        assert!(timer.is_scheduled());
+        let remaining = timer.remaining().expect("To have remaining time");
+        assert!(remaining > time::Duration::from_secs(10) && remaining <= time::Duration::from_secs(20));
+
+        assert!(timer.pause());
+        assert!(!timer.is_scheduled());
+        assert!(timer.resume());
+        let remaining = timer.remaining().expect("To have remaining time");
+        assert!(remaining > time::Duration::from_secs(10) && remaining <= time::Duration::from_secs(20));
+        timer.cancel();
+    }
+
+    #[test]
+    fn registry_reuses_slots() {
+        //Exceeds initial capacity of every shard, to grow it
+        let contexts = (1..=SHARDS * 20).map(Registry::insert).collect::<Vec<_>>();
+        for (idx, context) in contexts.iter().enumerate() {
+            let (shard, index, generation) = Registry::locate(*context);
+            let slot = &REGISTRY[shard].lock().slots[index];
+            assert_eq!(slot.state, idx + 1);
+            assert_eq!(slot.generation, generation);
+        }
+
+        let stale = contexts[0];
+        for context in contexts {
+            let (shard, index, _) = Registry::locate(context);
+            REGISTRY[shard].lock().remove(index);
+        }
+
+        //Slot of removed state is reused with new generation, so stale context doesn't match it
+        let (shard, index, generation) = Registry::locate(stale);
+        assert_ne!(REGISTRY[shard].lock().slots[index].generation, generation);
+    }
+
+    #[cfg(any(os_timer_backend = "posix", os_timer_backend = "win32", os_timer_backend = "apple"))]
+    #[test]
+    fn os_backend_dispatches_context() {
+        extern crate std;
+        use super::backend::{Os, TimerBackend, TimerContext};
+        use core::sync::atomic::AtomicUsize;
+
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        static THREAD: AtomicUsize = AtomicUsize::new(0);
+
+        fn cb() {
+            COUNT.fetch_add(1, Ordering::SeqCst);
+            THREAD.store(Os.thread_id(), Ordering::SeqCst);
+        }
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        //Plan must be armed for expiration to reach callback
+        assert!(timer.schedule_once(time::Duration::from_secs(60)));
+
+        let before = Os.now();
+        unsafe {
+            let handle = Os.create(TimerContext::new(timer.get_inner().context));
+            assert_ne!(handle, 0);
+            assert!(!Os.get(handle));
+
+            assert!(Os.set(handle, time::Duration::from_secs(60), time::Duration::ZERO, time::Duration::ZERO));
+            assert!(Os.get(handle));
+            Os.disarm(handle);
+            assert!(!Os.get(handle));
+
+            assert!(Os.set(handle, time::Duration::from_secs(60), time::Duration::ZERO, time::Duration::ZERO));
+            assert!(Os.set(handle, time::Duration::ZERO, time::Duration::ZERO, time::Duration::ZERO));
+            assert!(!Os.get(handle));
+
+            assert!(Os.set(handle, time::Duration::from_millis(10), time::Duration::ZERO, time::Duration::ZERO));
+            for _ in 0..100 {
+                if COUNT.load(Ordering::SeqCst) != 0 {
+                    break;
+                }
+                std::thread::sleep(time::Duration::from_millis(10));
+            }
+            Os.wait(handle);
+            Os.delete(handle);
+        }
+
+        assert_eq!(COUNT.load(Ordering::SeqCst), 1);
+        assert_ne!(THREAD.load(Ordering::SeqCst), Os.thread_id());
+        assert!(Os.now() >= before + time::Duration::from_millis(10));
+        timer.cancel();
+    }
+}
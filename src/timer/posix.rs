@@ -1,12 +1,8 @@
 use core::{ptr, time, mem};
-use core::cell::Cell;
-use core::sync::atomic::{AtomicUsize, Ordering};
-use super::BoxFnPtr;
+use super::State;
+use super::backend::{TimerBackend, TimerContext};
 
-extern crate alloc;
-use alloc::boxed::Box;
-
-mod ffi {
+pub(crate) mod ffi {
     use core::mem;
     pub use libc::c_void;
     #[allow(non_camel_case_types)]
@@ -45,6 +41,13 @@ mod ffi {
         pub tv_nsec: libc::c_long,
     }
 
+    impl timespec {
+        #[inline(always)]
+        pub fn as_duration(&self) -> core::time::Duration {
+            core::time::Duration::new(self.tv_sec as u64, self.tv_nsec as u32)
+        }
+    }
+
     #[repr(C)]
     #[derive(PartialEq)]
     pub struct itimerspec {
@@ -75,277 +78,154 @@ mod ffi {
     }
 }
 
-enum CallbackVariant {
-    Trivial(*mut ffi::c_void),
-    Boxed(Box<dyn FnMut()>),
-}
+pub(crate) use ffi::{timer_callback, timer_callback_unsafe, timer_callback_generic};
 
-///Timer's callback abstraction
-pub struct Callback {
-    variant: CallbackVariant,
-    ffi_cb: ffi::Callback,
+unsafe extern "C" fn state_callback(value: libc::sigval) {
+    State::on_fire(value.sival_ptr as usize, |cb, data| {
+        (cb)(libc::sigval {
+            sival_ptr: data,
+        })
+    });
 }
 
-impl Callback {
-    ///Creates raw callback for platform timer.
-    ///
-    ///Signature depends on platform.
-    pub unsafe fn raw(ffi_cb: ffi::Callback, data: *mut ffi::c_void) -> Self {
-        Self {
-            variant: CallbackVariant::Trivial(data),
-            ffi_cb,
-        }
-    }
-
-    ///Creates callback using plain rust function
-    pub fn plain(cb: fn()) -> Self {
-        Self {
-            variant: CallbackVariant::Trivial(cb as _),
-            ffi_cb: ffi::timer_callback,
-        }
+#[inline(always)]
+fn to_timespec(value: time::Duration) -> ffi::timespec {
+    ffi::timespec {
+        tv_sec: value.as_secs() as libc::time_t,
+        #[cfg(not(any(target_os = "openbsd", target_os = "netbsd")))]
+        tv_nsec: value.subsec_nanos() as libc::suseconds_t,
+        #[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+        tv_nsec: value.subsec_nanos() as libc::c_long,
     }
+}
 
-    ///Creates callback using plain unsafe function
-    pub fn unsafe_plain(cb: unsafe fn()) -> Self {
-        Self {
-            variant: CallbackVariant::Trivial(cb as _),
-            ffi_cb: ffi::timer_callback_unsafe,
-        }
-    }
+///Invokes timer's callback, same as on expiration.
+pub(crate) unsafe fn dispatch(context: usize) {
+    state_callback(libc::sigval {
+        sival_ptr: context as *mut ffi::c_void,
+    })
+}
 
-    ///Creates callback using closure, storing it on heap.
-    pub fn closure<F: 'static + FnMut()>(cb: F) -> Self {
-        Self {
-            variant: CallbackVariant::Boxed(Box::new(cb)),
-            ffi_cb: ffi::timer_callback_generic::<F>,
-        }
-    }
+#[inline(always)]
+///Returns `timer_t` of the timer.
+pub(crate) unsafe fn as_raw(handle: usize) -> *mut ffi::c_void {
+    handle as _
 }
 
-///Posix timer wrapper
-pub struct Timer {
-    inner: AtomicUsize,
-    data: Cell<BoxFnPtr>,
+#[inline(always)]
+///Takes ownership of `timer_t`, created elsewhere.
+pub(crate) unsafe fn adopt(raw: *mut ffi::c_void) -> usize {
+    raw as usize
 }
 
-impl Timer {
-    #[inline]
-    ///Creates new uninitialized instance.
-    ///
-    ///In order to use it one must call `init`.
-    pub const unsafe fn uninit() -> Self {
-        Self {
-            inner: AtomicUsize::new(0),
-            data: Cell::new(BoxFnPtr::null()),
-        }
-    }
+///Returns remaining time until next alarm and interval of armed timer, as reported by `timer_gettime`.
+pub(crate) unsafe fn remaining(handle: usize) -> Option<(time::Duration, time::Duration)> {
+    let mut curr_value = mem::MaybeUninit::<ffi::itimerspec>::uninit();
 
-    #[inline(always)]
-    fn get_inner(&self) -> usize {
-        let inner = self.inner.load(Ordering::Acquire);
-        debug_assert_ne!(inner, 0, "Timer has not been initialized");
-        inner
+    if ffi::timer_gettime(handle, curr_value.as_mut_ptr()) != 0 {
+        return None;
     }
 
-    #[inline(always)]
-    ///Returns whether timer is initialized
-    pub fn is_init(&self) -> bool {
-        self.inner.load(Ordering::Acquire) != 0
+    let curr_value = curr_value.assume_init();
+    match curr_value.it_value == ffi::ZERO_TIMER_DURATION.it_value {
+        true => None,
+        false => Some((curr_value.it_value.as_duration(), curr_value.it_interval.as_duration())),
     }
+}
 
-    #[must_use]
-    ///Performs timer initialization
-    ///
-    ///`cb` pointer to function to invoke when timer expires.
-    ///
-    ///Returns whether timer has been initialized successfully or not.
-    ///
-    ///If timer is already initialized does nothing, returning false.
-    pub fn init(&self, cb: Callback) -> bool {
-        if self.is_init() {
-            return false;
-        }
-
-        let ffi_cb = cb.ffi_cb;
-        let (data, ffi_data) = match cb.variant {
-            CallbackVariant::Trivial(data) => (BoxFnPtr::null(), data),
-            CallbackVariant::Boxed(cb) => unsafe {
-                let raw = Box::into_raw(cb);
-                (BoxFnPtr(mem::transmute(raw)), raw as *mut ffi::c_void)
-            },
-        };
-
-        let handle = unsafe {
-            ffi::posix_timer(libc::CLOCK_MONOTONIC, ffi_cb, ffi_data)
-        };
+///Disarms timer, returning remaining time until next alarm and interval it had.
+///
+///Both are read by the same `timer_settime`, so alarm cannot slip in between.
+pub(crate) unsafe fn pause(handle: usize) -> Option<(time::Duration, time::Duration)> {
+    let mut old_value = mem::MaybeUninit::<ffi::itimerspec>::uninit();
 
-        match self.inner.compare_exchange(0, handle, Ordering::SeqCst, Ordering::Acquire) {
-            Ok(_) => match handle {
-                0 => false,
-                _ => {
-                    //safe because we can never reach here once `handle.is_null() != true`
-                    self.data.set(data);
-                    true
-                },
-            },
-            Err(_) => {
-                unsafe {
-                    ffi::timer_delete(handle);
-                }
-                false
-            }
-        }
+    if ffi::timer_settime(handle, 0, &ffi::ZERO_TIMER_DURATION, old_value.as_mut_ptr()) != 0 {
+        return None;
     }
 
-    ///Creates new timer, invoking provided `cb` when timer expires.
-    ///
-    ///On failure, returns `None`
-    pub fn new(cb: Callback) -> Option<Self> {
-        let ffi_cb = cb.ffi_cb;
-        let (data, ffi_data) = match cb.variant {
-            CallbackVariant::Trivial(data) => (BoxFnPtr::null(), data),
-            CallbackVariant::Boxed(cb) => unsafe {
-                let raw = Box::into_raw(cb);
-                (BoxFnPtr(mem::transmute(raw)), raw as *mut ffi::c_void)
-            },
-        };
-
-        let handle = unsafe {
-            ffi::posix_timer(libc::CLOCK_MONOTONIC, ffi_cb, ffi_data)
-        };
-
-        if handle == 0 {
-            return None;
-        }
-
-        Some(Self {
-            inner: AtomicUsize::new(handle),
-            data: Cell::new(data),
-        })
+    let old_value = old_value.assume_init();
+    match old_value.it_value == ffi::ZERO_TIMER_DURATION.it_value {
+        true => None,
+        false => Some((old_value.it_value.as_duration(), old_value.it_interval.as_duration())),
     }
+}
 
-    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`.
-    ///
-    ///Note that if timer has been scheduled before, but hasn't expire yet, behaviour is undefined (Callback may or may not be called).
-    ///To prevent that user must `cancel` timer first.
-    ///
-    ///Returns `true` if successfully set, otherwise on error returns `false`
-    pub fn schedule_interval(&self, timeout: time::Duration, interval: time::Duration) -> bool {
-        let it_value = ffi::timespec {
-            tv_sec: timeout.as_secs() as libc::time_t,
-            #[cfg(not(any(target_os = "openbsd", target_os = "netbsd")))]
-            tv_nsec: timeout.subsec_nanos() as libc::suseconds_t,
-            #[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
-            tv_nsec: timeout.subsec_nanos() as libc::c_long,
-        };
-
-        let it_interval = ffi::timespec {
-            tv_sec: interval.as_secs() as libc::time_t,
-            #[cfg(not(any(target_os = "openbsd", target_os = "netbsd")))]
-            tv_nsec: interval.subsec_nanos() as libc::suseconds_t,
-            #[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
-            tv_nsec: interval.subsec_nanos() as libc::c_long,
-        };
-
-        let new_value = ffi::itimerspec {
-            it_interval,
-            it_value,
-        };
-
+#[inline]
+///Applies `leeway` as timer slack of current callback's thread.
+///
+///Every notification runs on new thread, so it has to be applied on each invocation.
+///
+///Slack only affects sleeps and waits made by callback, not expiration of timer itself.
+pub(crate) fn apply_leeway(leeway: time::Duration) {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    if leeway != time::Duration::ZERO {
+        let slack = core::cmp::min(leeway.as_nanos(), libc::c_ulong::max_value() as u128) as libc::c_ulong;
         unsafe {
-            ffi::timer_settime(self.get_inner(), 0, &new_value, ptr::null_mut()) == 0
+            libc::prctl(libc::PR_SET_TIMERSLACK, slack);
         }
     }
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    let _ = leeway;
+}
 
+///POSIX timers backend.
+pub struct Posix;
+
+///Backend, used by timers.
+pub(crate) const BACKEND: Posix = Posix;
+
+unsafe impl TimerBackend for Posix {
     #[inline]
-    ///Returns `true` if timer has been scheduled and still pending.
-    ///
-    ///On Win/Mac it only returns whether timer has been scheduled, as there is no way to check
-    ///whether timer is ongoing
-    pub fn is_scheduled(&self) -> bool {
-        let handle = self.get_inner();
-        let curr_value = unsafe {
-            let mut curr_value = mem::MaybeUninit::<ffi::itimerspec>::uninit();
-
-            if ffi::timer_gettime(handle, curr_value.as_mut_ptr()) != 0 {
-                return false;
-            }
-            curr_value.assume_init()
+    fn now(&self) -> time::Duration {
+        let mut ts = mem::MaybeUninit::<libc::timespec>::uninit();
+        let ts = unsafe {
+            libc::clock_gettime(libc::CLOCK_MONOTONIC, ts.as_mut_ptr());
+            ts.assume_init()
         };
 
-        curr_value != ffi::ZERO_TIMER_DURATION
+        time::Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
     }
 
-    #[inline]
-    ///Cancels ongoing timer, if it was scheduled.
-    pub fn cancel(&self) {
-        if self.is_scheduled() {
-            unsafe {
-                ffi::timer_settime(self.get_inner(), 0, &mem::MaybeUninit::zeroed().assume_init(), ptr::null_mut());
-            }
-        }
+    #[inline(always)]
+    unsafe fn create(&self, context: TimerContext) -> usize {
+        ffi::posix_timer(libc::CLOCK_MONOTONIC, state_callback, context.get() as *mut ffi::c_void)
     }
-}
 
-impl Drop for Timer {
-    fn drop(&mut self) {
-        let handle = self.inner.load(Ordering::Relaxed);
-        if handle != 0 {
-            self.cancel();
-            unsafe {
-                ffi::timer_delete(handle)
-            }
-        }
-    }
-}
+    ///POSIX timers have no leeway, so alarm is never deferred. See `apply_leeway` instead.
+    unsafe fn set(&self, handle: usize, timeout: time::Duration, interval: time::Duration, _leeway: time::Duration) -> bool {
+        let new_value = ffi::itimerspec {
+            it_interval: to_timespec(interval),
+            it_value: to_timespec(timeout),
+        };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        ffi::timer_settime(handle, 0, &new_value, ptr::null_mut()) == 0
+    }
 
-    #[test]
-    fn init_plain_fn() {
-        let mut timer = unsafe {
-            Timer::uninit()
-        };
+    ///Returns `true` if timer is armed and still pending.
+    unsafe fn get(&self, handle: usize) -> bool {
+        let mut curr_value = mem::MaybeUninit::<ffi::itimerspec>::uninit();
 
-        fn cb() {
+        if ffi::timer_gettime(handle, curr_value.as_mut_ptr()) != 0 {
+            return false;
         }
 
-        let closure = || {
-        };
-
-        assert!(timer.init(Callback::plain(cb)));
-        let ptr = timer.inner.load(Ordering::Relaxed);
-        assert_ne!(ptr, 0);
-        assert!(timer.data.get_mut().is_null());
+        curr_value.assume_init() != ffi::ZERO_TIMER_DURATION
+    }
 
-        assert!(!timer.init(Callback::closure(closure)));
-        assert_ne!(ptr, 0);
-        assert_eq!(ptr, timer.inner.load(Ordering::Relaxed));
-        assert!(timer.data.get_mut().is_null());
+    #[inline]
+    unsafe fn disarm(&self, handle: usize) {
+        ffi::timer_settime(handle, 0, &ffi::ZERO_TIMER_DURATION, ptr::null_mut());
     }
 
-    #[test]
-    fn init_closure() {
-        let mut timer = unsafe {
-            Timer::uninit()
-        };
+    #[inline]
+    unsafe fn delete(&self, handle: usize) {
+        ffi::timer_delete(handle)
+    }
 
-        fn cb() {
+    #[inline(always)]
+    fn thread_id(&self) -> usize {
+        unsafe {
+            libc::pthread_self() as usize
         }
-
-        let closure = || {
-        };
-
-        assert!(timer.init(Callback::closure(closure)));
-        let ptr = timer.inner.load(Ordering::Relaxed);
-        assert_ne!(ptr, 0);
-        assert!(!timer.data.get_mut().is_null());
-
-        assert!(!timer.init(Callback::plain(cb)));
-        assert_ne!(ptr, 0);
-        assert_eq!(ptr, timer.inner.load(Ordering::Relaxed));
-        assert!(!timer.data.get_mut().is_null());
     }
 }
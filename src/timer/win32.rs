@@ -1,12 +1,8 @@
 use core::{time, ptr, mem};
-use core::cell::Cell;
-use core::sync::atomic::{AtomicPtr, Ordering};
-use super::BoxFnPtr;
+use super::State;
+use super::backend::{TimerBackend, TimerContext};
 
-extern crate alloc;
-use alloc::boxed::Box;
-
-mod ffi {
+pub(crate) mod ffi {
     pub use core::ffi::c_void;
 
     type DWORD = u32;
@@ -26,10 +22,13 @@ mod ffi {
         pub fn SetThreadpoolTimerEx(timer: *mut c_void, pftDueTime: *mut FileTime, msPeriod: DWORD, msWindowLength: DWORD) -> BOOL;
         pub fn IsThreadpoolTimerSet(timer: *mut c_void) -> BOOL;
         pub fn WaitForThreadpoolTimerCallbacks(timer: *mut c_void, fCancelPendingCallbacks: BOOL);
+        pub fn QueryPerformanceCounter(lpPerformanceCount: *mut i64) -> BOOL;
+        pub fn QueryPerformanceFrequency(lpFrequency: *mut i64) -> BOOL;
+        pub fn GetCurrentThreadId() -> DWORD;
     }
 }
 
-unsafe extern "system" fn timer_callback(_: *mut ffi::c_void, data: *mut ffi::c_void, _: *mut ffi::c_void) {
+pub(crate) unsafe extern "system" fn timer_callback(_: *mut ffi::c_void, data: *mut ffi::c_void, _: *mut ffi::c_void) {
     if !data.is_null() {
         let cb: fn() -> () = mem::transmute(data);
 
@@ -37,7 +36,7 @@ unsafe extern "system" fn timer_callback(_: *mut ffi::c_void, data: *mut ffi::c_
     }
 }
 
-unsafe extern "system" fn timer_callback_unsafe(_: *mut ffi::c_void, data: *mut ffi::c_void, _: *mut ffi::c_void) {
+pub(crate) unsafe extern "system" fn timer_callback_unsafe(_: *mut ffi::c_void, data: *mut ffi::c_void, _: *mut ffi::c_void) {
     if !data.is_null() {
         let cb: unsafe fn() -> () = mem::transmute(data);
 
@@ -45,7 +44,7 @@ unsafe extern "system" fn timer_callback_unsafe(_: *mut ffi::c_void, data: *mut
     }
 }
 
-unsafe extern "system" fn timer_callback_generic<T: FnMut() -> ()>(_: *mut ffi::c_void, data: *mut ffi::c_void, _: *mut ffi::c_void) {
+pub(crate) unsafe extern "system" fn timer_callback_generic<T: FnMut() -> ()>(_: *mut ffi::c_void, data: *mut ffi::c_void, _: *mut ffi::c_void) {
     if !data.is_null() {
         let cb = &mut *(data as *mut T);
 
@@ -53,262 +52,122 @@ unsafe extern "system" fn timer_callback_generic<T: FnMut() -> ()>(_: *mut ffi::
     }
 }
 
-enum CallbackVariant {
-    Trivial(*mut ffi::c_void),
-    Boxed(Box<dyn FnMut()>),
+unsafe extern "system" fn state_callback(cb_inst: *mut ffi::c_void, ctx: *mut ffi::c_void, timer: *mut ffi::c_void) {
+    State::on_fire(ctx as usize, |cb, data| {
+        (cb)(cb_inst, data, timer)
+    });
 }
 
-///Timer's callback abstraction
-pub struct Callback {
-    variant: CallbackVariant,
-    ffi_cb: ffi::Callback,
+///Invokes timer's callback, same as on expiration.
+///
+///Raw callbacks receive null callback instance and timer.
+pub(crate) unsafe fn dispatch(context: usize) {
+    state_callback(ptr::null_mut(), context as *mut ffi::c_void, ptr::null_mut())
 }
 
-impl Callback {
-    ///Creates raw callback for platform timer.
-    ///
-    ///Signature depends on platform.
-    pub unsafe fn raw(ffi_cb: ffi::Callback, data: *mut ffi::c_void) -> Self {
-        Self {
-            variant: CallbackVariant::Trivial(data),
-            ffi_cb,
-        }
-    }
+#[inline(always)]
+///Returns `PTP_TIMER` of the timer.
+pub(crate) unsafe fn as_raw(handle: usize) -> *mut ffi::c_void {
+    handle as _
+}
 
-    ///Creates callback using plain rust function
-    pub fn plain(cb: fn()) -> Self {
-        Self {
-            variant: CallbackVariant::Trivial(cb as _),
-            ffi_cb: timer_callback,
-        }
-    }
+#[inline(always)]
+///Takes ownership of `PTP_TIMER`, created elsewhere.
+pub(crate) unsafe fn adopt(raw: *mut ffi::c_void) -> usize {
+    raw as usize
+}
 
-    ///Creates callback using plain unsafe function
-    pub fn unsafe_plain(cb: unsafe fn()) -> Self {
-        Self {
-            variant: CallbackVariant::Trivial(cb as _),
-            ffi_cb: timer_callback_unsafe,
-        }
-    }
+#[inline(always)]
+///Returns remaining time until next alarm and interval of armed timer, which OS doesn't report.
+pub(crate) unsafe fn remaining(_handle: usize) -> Option<(time::Duration, time::Duration)> {
+    None
+}
 
-    ///Creates callback using closure, storing it on heap.
-    pub fn closure<F: 'static + FnMut()>(cb: F) -> Self {
-        Self {
-            variant: CallbackVariant::Boxed(Box::new(cb)),
-            ffi_cb: timer_callback_generic::<F>,
-        }
-    }
+#[inline]
+///Disarms timer, returning remaining time until next alarm and interval it had, which OS doesn't report.
+pub(crate) unsafe fn pause(handle: usize) -> Option<(time::Duration, time::Duration)> {
+    BACKEND.disarm(handle);
+    None
 }
 
-///Windows thread pool timer
-pub struct Timer {
-    inner: AtomicPtr<ffi::c_void>,
-    data: Cell<BoxFnPtr>,
+#[inline(always)]
+///Applies `leeway` to callback's thread, not needed as it is passed to the timer.
+pub(crate) fn apply_leeway(_leeway: time::Duration) {
 }
 
-impl Timer {
-    #[inline]
-    ///Creates new uninitialized instance.
-    ///
-    ///In order to use it one must call `init`.
-    pub const unsafe fn uninit() -> Self {
-        Self {
-            inner: AtomicPtr::new(ptr::null_mut()),
-            data: Cell::new(BoxFnPtr::null()),
+///Thread pool timers backend.
+pub struct Win32;
+
+///Backend, used by timers.
+pub(crate) const BACKEND: Win32 = Win32;
+
+unsafe impl TimerBackend for Win32 {
+    fn now(&self) -> time::Duration {
+        let mut counter = 0i64;
+        let mut frequency = 0i64;
+        unsafe {
+            ffi::QueryPerformanceCounter(&mut counter);
+            ffi::QueryPerformanceFrequency(&mut frequency);
         }
-    }
 
-    #[inline(always)]
-    fn get_inner(&self) -> *mut ffi::c_void {
-        let inner = self.inner.load(Ordering::Acquire);
-        debug_assert!(!inner.is_null(), "Timer has not been initialized");
-        inner
+        let counter = counter as u64;
+        let frequency = frequency as u64;
+        let secs = counter / frequency;
+        let nanos = (counter % frequency) * 1_000_000_000 / frequency;
+        time::Duration::new(secs, nanos as u32)
     }
 
     #[inline(always)]
-    ///Returns whether timer is initialized
-    pub fn is_init(&self) -> bool {
-        !self.inner.load(Ordering::Acquire).is_null()
+    unsafe fn create(&self, context: TimerContext) -> usize {
+        ffi::CreateThreadpoolTimer(state_callback, context.get() as *mut ffi::c_void, ptr::null_mut()) as usize
     }
 
-    #[must_use]
-    ///Performs timer initialization
-    ///
-    ///`cb` is variant of callback to invoke when timer expires
-    ///
-    ///Returns whether timer has been initialized successfully or not.
-    ///
-    ///If timer is already initialized does nothing, returning false.
-    pub fn init(&self, cb: Callback) -> bool {
-        if self.is_init() {
-            return false;
+    ///`interval` and `leeway` are truncated to milliseconds and by `u32::max_value()`
+    unsafe fn set(&self, handle: usize, timeout: time::Duration, interval: time::Duration, leeway: time::Duration) -> bool {
+        //OS fires immediately on zero timeout
+        if timeout == time::Duration::ZERO {
+            self.disarm(handle);
+            return true;
         }
 
-        let ffi_cb = cb.ffi_cb;
-        let (data, ffi_data) = match cb.variant {
-            CallbackVariant::Trivial(data) => (BoxFnPtr::null(), data),
-            CallbackVariant::Boxed(cb) => unsafe {
-                let raw = Box::into_raw(cb);
-                (BoxFnPtr(mem::transmute(raw)), raw as *mut ffi::c_void)
-            },
-        };
-
-        let handle = unsafe {
-            ffi::CreateThreadpoolTimer(ffi_cb, ffi_data, ptr::null_mut())
-        };
-
-        match self.inner.compare_exchange(ptr::null_mut(), handle, Ordering::SeqCst, Ordering::Acquire) {
-            Ok(_) => match handle.is_null() {
-                true => false,
-                false => {
-                    //safe because we can never reach here once `handle.is_null() != true`
-                    self.data.set(data);
-                    true
-                },
-            },
-            Err(_) => {
-                unsafe {
-                    ffi::CloseThreadpoolTimer(handle);
-                }
-                false
-            }
-        }
-    }
-
-    ///Creates new timer, invoking provided `cb` when timer expires.
-    ///
-    ///On failure, returns `None`
-    pub fn new(cb: Callback) -> Option<Self> {
-        let ffi_cb = cb.ffi_cb;
-        let (data, ffi_data) = match cb.variant {
-            CallbackVariant::Trivial(data) => (BoxFnPtr::null(), data),
-            CallbackVariant::Boxed(cb) => unsafe {
-                let raw = Box::into_raw(cb);
-                (BoxFnPtr(mem::transmute(raw)), raw as *mut ffi::c_void)
-            },
-        };
-
-        let handle = unsafe {
-            ffi::CreateThreadpoolTimer(ffi_cb, ffi_data, ptr::null_mut())
-        };
-
-        if handle.is_null() {
-            return None;
-        }
-
-        Some(Self {
-            inner: AtomicPtr::new(handle),
-            data: Cell::new(data),
-        })
-    }
-
-    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`.
-    ///
-    ///Note that if timer has been scheduled before, but hasn't expire yet, behaviour is undefined (Callback may or may not be called).
-    ///To prevent that user must `cancel` timer first.
-    ///
-    ///# Note
-    ///
-    ///- `interval` is truncated by `u32::max_value()`
-    ///
-    ///Returns `true` if successfully set, otherwise on error returns `false`
-    pub fn schedule_interval(&self, timeout: time::Duration, interval: time::Duration) -> bool {
         let mut ticks = i64::from(timeout.subsec_nanos() / 100);
         ticks += (timeout.as_secs() * 10_000_000) as i64;
         let ticks = -ticks;
 
         let interval = interval.as_millis() as u32;
+        let leeway = leeway.as_millis() as u32;
 
-        unsafe {
-            let mut time: ffi::FileTime = mem::transmute(ticks);
-            ffi::SetThreadpoolTimerEx(self.get_inner(), &mut time, interval, 0);
-        }
+        let mut time = mem::transmute::<i64, ffi::FileTime>(ticks);
+        ffi::SetThreadpoolTimerEx(handle as _, &mut time, interval, leeway);
 
         true
     }
 
     #[inline]
-    ///Returns `true` if timer has been scheduled and still pending.
-    ///
-    ///On Win/Mac it only returns whether timer has been scheduled, as there is no way to check
-    ///whether timer is ongoing
-    pub fn is_scheduled(&self) -> bool {
-        let handle = self.get_inner();
-        unsafe {
-            ffi::IsThreadpoolTimerSet(handle) != 0
-        }
+    unsafe fn get(&self, handle: usize) -> bool {
+        ffi::IsThreadpoolTimerSet(handle as _) != 0
     }
 
     #[inline]
-    ///Cancels ongoing timer, if it was scheduled.
-    pub fn cancel(&self) {
-        let handle = self.get_inner();
-        unsafe {
-            ffi::SetThreadpoolTimerEx(handle, ptr::null_mut(), 0, 0);
-            ffi::WaitForThreadpoolTimerCallbacks(handle, 1);
-        }
+    unsafe fn disarm(&self, handle: usize) {
+        ffi::SetThreadpoolTimerEx(handle as _, ptr::null_mut(), 0, 0);
     }
-}
 
-impl Drop for Timer {
-    fn drop(&mut self) {
-        let handle = self.inner.load(Ordering::Relaxed);
-        if !handle.is_null() {
-            self.cancel();
-            unsafe {
-                ffi::CloseThreadpoolTimer(handle);
-            }
-        }
+    #[inline]
+    ///Waits for pending callbacks, cancelling queued ones.
+    unsafe fn wait(&self, handle: usize) {
+        ffi::WaitForThreadpoolTimerCallbacks(handle as _, 1);
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
-    #[test]
-    fn init_plain_fn() {
-        let mut timer = unsafe {
-            Timer::uninit()
-        };
-
-        fn cb() {
-        }
-
-        let closure = || {
-        };
-
-        assert!(timer.init(Callback::plain(cb)));
-        let ptr = timer.inner.load(Ordering::Relaxed);
-        assert!(!ptr.is_null());
-        assert!(timer.data.get_mut().is_null());
-
-        assert!(!timer.init(Callback::closure(closure)));
-        assert!(!ptr.is_null());
-        assert_eq!(ptr, timer.inner.load(Ordering::Relaxed));
-        assert!(timer.data.get_mut().is_null());
+    #[inline]
+    unsafe fn delete(&self, handle: usize) {
+        ffi::CloseThreadpoolTimer(handle as _);
     }
 
-    #[test]
-    fn init_closure() {
-        let mut timer = unsafe {
-            Timer::uninit()
-        };
-
-        fn cb() {
+    #[inline(always)]
+    fn thread_id(&self) -> usize {
+        unsafe {
+            ffi::GetCurrentThreadId() as usize
         }
-
-        let closure = || {
-        };
-
-        assert!(timer.init(Callback::closure(closure)));
-        let ptr = timer.inner.load(Ordering::Relaxed);
-        assert!(!ptr.is_null());
-        assert!(!timer.data.get_mut().is_null());
-
-        assert!(!timer.init(Callback::plain(cb)));
-        assert!(!ptr.is_null());
-        assert_eq!(ptr, timer.inner.load(Ordering::Relaxed));
-        assert!(!timer.data.get_mut().is_null());
     }
 }
@@ -0,0 +1,318 @@
+use core::{cmp, mem, time};
+
+use crate::Timer;
+use crate::timer::monotonic_now;
+use crate::sync::Lock;
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+const LEVEL_BITS: u32 = 6;
+const SLOTS: usize = 1 << LEVEL_BITS;
+const LEVELS: usize = 4;
+//Maximum number of ticks representable by wheel, longer timeouts are re-cascaded.
+const MAX_TICKS: u64 = 1 << (LEVEL_BITS as usize * LEVELS);
+const NIL: u32 = u32::max_value();
+
+type Job = Box<dyn FnOnce() + Send>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+///Handle to the timeout, scheduled on `TimerWheel`
+pub struct WheelId {
+    index: u32,
+    generation: u32,
+}
+
+struct Entry {
+    expires: u64,
+    //Index of slot in which entry resides, NIL if entry is free
+    slot: u32,
+    prev: u32,
+    next: u32,
+    generation: u32,
+    job: Option<Job>,
+}
+
+struct Wheel {
+    //Number of ticks passed
+    now: u64,
+    slots: [u32; SLOTS * LEVELS],
+    entries: Vec<Entry>,
+    //Head of free entries list, linked via `next`
+    free: u32,
+    len: usize,
+}
+
+impl Wheel {
+    fn new() -> Self {
+        Self {
+            now: 0,
+            slots: [NIL; SLOTS * LEVELS],
+            entries: Vec::new(),
+            free: NIL,
+            len: 0,
+        }
+    }
+
+    fn slot_for(&self, expires: u64) -> u32 {
+        let delta = expires.saturating_sub(self.now);
+        let expires = if delta >= MAX_TICKS {
+            self.now + MAX_TICKS - 1
+        } else {
+            expires
+        };
+
+        let mut level = 0;
+        while level < LEVELS - 1 && delta >= 1 << (LEVEL_BITS as usize * (level + 1)) {
+            level += 1;
+        }
+
+        let idx = (expires >> (LEVEL_BITS as usize * level)) as usize & (SLOTS - 1);
+        (level * SLOTS + idx) as u32
+    }
+
+    fn link(&mut self, index: u32) {
+        let slot = self.slot_for(self.entries[index as usize].expires);
+        let head = self.slots[slot as usize];
+
+        let entry = &mut self.entries[index as usize];
+        entry.slot = slot;
+        entry.prev = NIL;
+        entry.next = head;
+
+        if head != NIL {
+            self.entries[head as usize].prev = index;
+        }
+        self.slots[slot as usize] = index;
+    }
+
+    fn unlink(&mut self, index: u32) {
+        let (slot, prev, next) = {
+            let entry = &self.entries[index as usize];
+            (entry.slot, entry.prev, entry.next)
+        };
+
+        match prev {
+            NIL => self.slots[slot as usize] = next,
+            prev => self.entries[prev as usize].next = next,
+        }
+        if next != NIL {
+            self.entries[next as usize].prev = prev;
+        }
+    }
+
+    fn insert(&mut self, ticks: u64, job: Job) -> WheelId {
+        let expires = self.now.saturating_add(ticks);
+        let index = match self.free {
+            NIL => {
+                self.entries.push(Entry {
+                    expires,
+                    slot: NIL,
+                    prev: NIL,
+                    next: NIL,
+                    generation: 0,
+                    job: Some(job),
+                });
+                (self.entries.len() - 1) as u32
+            },
+            index => {
+                let entry = &mut self.entries[index as usize];
+                self.free = entry.next;
+                entry.expires = expires;
+                entry.job = Some(job);
+                index
+            }
+        };
+
+        self.link(index);
+        self.len += 1;
+
+        WheelId {
+            index,
+            generation: self.entries[index as usize].generation,
+        }
+    }
+
+    fn release(&mut self, index: u32) -> Option<Job> {
+        self.unlink(index);
+        self.len -= 1;
+
+        let entry = &mut self.entries[index as usize];
+        entry.slot = NIL;
+        entry.prev = NIL;
+        entry.next = self.free;
+        entry.generation = entry.generation.wrapping_add(1);
+        self.free = index;
+        entry.job.take()
+    }
+
+    fn cancel(&mut self, id: WheelId) -> bool {
+        match self.entries.get(id.index as usize) {
+            Some(entry) if entry.generation == id.generation && entry.slot != NIL => {
+                drop(self.release(id.index));
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn take_slot(&mut self, slot: usize) -> u32 {
+        mem::replace(&mut self.slots[slot], NIL)
+    }
+
+    ///Advances wheel by one tick, collecting expired jobs.
+    fn advance(&mut self, expired: &mut Vec<Job>) {
+        self.now += 1;
+
+        //Cascade entries from upper levels, starting with the highest one.
+        let mut level = LEVELS - 1;
+        while level > 0 {
+            let shift = LEVEL_BITS as usize * level;
+            if self.now & ((1 << shift) - 1) == 0 {
+                let idx = (self.now >> shift) as usize & (SLOTS - 1);
+                let mut index = self.take_slot(level * SLOTS + idx);
+                while index != NIL {
+                    let next = self.entries[index as usize].next;
+                    self.link(index);
+                    index = next;
+                }
+            }
+            level -= 1;
+        }
+
+        let idx = self.now as usize & (SLOTS - 1);
+        let mut index = self.slots[idx];
+        while index != NIL {
+            let next = self.entries[index as usize].next;
+            if let Some(job) = self.release(index) {
+                expired.push(job);
+            }
+            index = next;
+        }
+    }
+}
+
+struct WheelState {
+    timer: Timer,
+    tick: time::Duration,
+    //Time of wheel's start, according to which ticks are counted
+    start: time::Duration,
+    wheel: Lock<Wheel>,
+}
+
+impl WheelState {
+    ///Returns number of whole ticks, elapsed since start, and time, elapsed within current tick.
+    fn elapsed(&self) -> (u64, u128) {
+        let elapsed = monotonic_now().saturating_sub(self.start).as_nanos();
+        let tick = self.tick.as_nanos();
+        ((elapsed / tick) as u64, elapsed % tick)
+    }
+
+    fn on_tick(&self) {
+        let mut expired = Vec::new();
+        let (ticks, _) = self.elapsed();
+        {
+            //Expirations can be merged by OS on overrun, so catch up with elapsed time
+            let mut wheel = self.wheel.lock();
+            while wheel.now < ticks {
+                wheel.advance(&mut expired);
+            }
+        }
+
+        //Run outside of lock, so that jobs can schedule new timeouts.
+        for job in expired {
+            job();
+        }
+    }
+}
+
+///Hierarchical timing wheel, multiplexing many timeouts onto single OS timer.
+///
+///Timer ticks periodically with configured `tick`, so timeouts are only as precise as `tick`.
+///Insertion and cancellation are `O(1)`, while each tick only processes expired timeouts and
+///occasionally cascades timeouts from upper levels.
+///
+///Jobs are invoked on timer's thread.
+pub struct TimerWheel {
+    state: Arc<WheelState>,
+}
+
+impl TimerWheel {
+    ///Creates new wheel, ticking every `tick`.
+    ///
+    ///On failure to create or schedule timer, returns `None`
+    pub fn new(tick: time::Duration) -> Option<Self> {
+        assert!(!(tick.as_secs() == 0 && tick.as_nanos() == 0), "Cannot set zero tick");
+
+        let state = Arc::new(WheelState {
+            timer: unsafe {
+                Timer::uninit()
+            },
+            tick,
+            start: monotonic_now(),
+            wheel: Lock::new(Wheel::new()),
+        });
+
+        if !state.timer.init_owned(&state, WheelState::on_tick) {
+            return None;
+        }
+
+        match state.timer.schedule_interval(tick, tick) {
+            true => Some(Self {
+                state,
+            }),
+            false => None,
+        }
+    }
+
+    #[inline]
+    ///Returns tick duration.
+    pub fn tick(&self) -> time::Duration {
+        self.state.tick
+    }
+
+    ///Schedules `job` to run once `timeout` passes, rounded up to the next tick.
+    pub fn insert<F: FnOnce() + Send + 'static>(&self, timeout: time::Duration, job: F) -> WheelId {
+        let tick = self.state.tick.as_nanos();
+        let (now, partial) = self.state.elapsed();
+
+        //Count from start of current tick, including part of it that already passed
+        let ticks = (partial + timeout.as_nanos()).div_ceil(tick);
+        let ticks = match ticks > u64::max_value() as u128 {
+            true => u64::max_value(),
+            false => ticks as u64,
+        };
+        //Wheel can lag behind by pending ticks
+        let mut wheel = self.state.wheel.lock();
+        let ticks = cmp::max(now.saturating_add(ticks).saturating_sub(wheel.now), 1);
+
+        wheel.insert(ticks, Box::new(job))
+    }
+
+    #[inline]
+    ///Cancels timeout, returning whether it was still pending.
+    pub fn cancel(&self, id: WheelId) -> bool {
+        self.state.wheel.lock().cancel(id)
+    }
+
+    #[inline]
+    ///Returns number of pending timeouts.
+    pub fn len(&self) -> usize {
+        self.state.wheel.lock().len
+    }
+
+    #[inline]
+    ///Returns whether there are no pending timeouts.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Drop for TimerWheel {
+    #[inline]
+    fn drop(&mut self) {
+        self.state.timer.close();
+    }
+}
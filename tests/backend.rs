@@ -0,0 +1,105 @@
+#![cfg(all(feature = "custom-backend", not(feature = "mock")))]
+
+use os_timer::backend::{self, TimerBackend, TimerContext};
+use os_timer::{Callback, Timer};
+
+use core::time;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct Entry {
+    context: TimerContext,
+    armed: bool,
+}
+
+//Simulator, which fires timers only when asked
+struct Manual {
+    timers: Mutex<Vec<Option<Entry>>>,
+}
+
+impl Manual {
+    fn fire(&self, handle: usize) {
+        let context = match self.timers.lock().unwrap()[handle - 1].as_ref() {
+            Some(entry) if entry.armed => entry.context,
+            _ => return,
+        };
+
+        unsafe {
+            context.fire();
+        }
+    }
+}
+
+unsafe impl TimerBackend for Manual {
+    fn now(&self) -> time::Duration {
+        time::Duration::ZERO
+    }
+
+    unsafe fn create(&self, context: TimerContext) -> usize {
+        let mut timers = self.timers.lock().unwrap();
+        timers.push(Some(Entry {
+            context,
+            armed: false,
+        }));
+        timers.len()
+    }
+
+    unsafe fn set(&self, handle: usize, timeout: time::Duration, _interval: time::Duration, _leeway: time::Duration) -> bool {
+        if let Some(entry) = self.timers.lock().unwrap()[handle - 1].as_mut() {
+            entry.armed = !timeout.is_zero();
+        }
+        true
+    }
+
+    unsafe fn get(&self, handle: usize) -> bool {
+        self.timers.lock().unwrap()[handle - 1].as_ref().is_some_and(|entry| entry.armed)
+    }
+
+    unsafe fn delete(&self, handle: usize) {
+        self.timers.lock().unwrap()[handle - 1] = None;
+    }
+
+    fn thread_id(&self) -> usize {
+        std::thread_local! {
+            static ID: u8 = const { 0 };
+        }
+
+        ID.with(|id| id as *const u8 as usize)
+    }
+}
+
+static MANUAL: Manual = Manual {
+    timers: Mutex::new(Vec::new()),
+};
+
+#[test]
+fn custom_backend_dispatches_callback() {
+    assert!(backend::install(&MANUAL));
+    assert!(!backend::install(&MANUAL));
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let timer = {
+        let count = count.clone();
+        Timer::new(Callback::closure(move || {
+            count.fetch_add(1, Ordering::SeqCst);
+        })).expect("To create timer")
+    };
+    let handle = timer.as_raw() as usize;
+
+    MANUAL.fire(handle);
+    assert_eq!(count.load(Ordering::SeqCst), 0);
+
+    assert!(timer.schedule_interval(time::Duration::from_millis(10), time::Duration::from_millis(10)));
+    assert!(timer.is_scheduled());
+    MANUAL.fire(handle);
+    MANUAL.fire(handle);
+    assert_eq!(count.load(Ordering::SeqCst), 2);
+
+    timer.cancel();
+    assert!(!timer.is_scheduled());
+    MANUAL.fire(handle);
+    assert_eq!(count.load(Ordering::SeqCst), 2);
+
+    drop(timer);
+    assert!(MANUAL.timers.lock().unwrap()[handle - 1].is_none());
+}
@@ -0,0 +1,135 @@
+#![cfg(not(any(feature = "mock", feature = "custom-backend")))]
+
+use os_timer::{Backoff, Jitter, Retry};
+
+use core::time;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+#[test]
+fn backoff_retry_until_success() {
+    static ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+    let outcome = Arc::new(Mutex::new(None));
+    let result = outcome.clone();
+
+    let op = || match ATTEMPTS.fetch_add(1, Ordering::AcqRel) + 1 {
+        attempt if attempt < 3 => Err(attempt),
+        attempt => Ok(attempt),
+    };
+
+    let retry = Backoff::new(time::Duration::from_millis(100)).retry(op, move |res| {
+        *result.lock().unwrap() = Some(res);
+    }).expect("To start retry");
+
+    //First attempt is immediate
+    assert_eq!(retry.attempts(), 1);
+    assert!(!retry.is_finished());
+
+    //Second after 100ms, third after 200ms more
+    std::thread::sleep(time::Duration::from_millis(150));
+    assert_eq!(retry.attempts(), 2);
+    assert!(outcome.lock().unwrap().is_none());
+
+    std::thread::sleep(time::Duration::from_millis(250));
+    assert_eq!(retry.attempts(), 3);
+    assert!(retry.is_finished());
+    assert_eq!(*outcome.lock().unwrap(), Some(Ok(3)));
+}
+
+#[test]
+fn backoff_retry_exhausts_attempts() {
+    let outcome = Arc::new(Mutex::new(None));
+    let result = outcome.clone();
+
+    let retry = Backoff::new(time::Duration::from_millis(50)).factor(1.0)
+                                                              .jitter(Jitter::Full)
+                                                              .max_attempts(4)
+                                                              .retry(|| Err::<(), _>("fail"), move |res| {
+        *result.lock().unwrap() = Some(res);
+    }).expect("To start retry");
+
+    std::thread::sleep(time::Duration::from_millis(400));
+    assert_eq!(retry.attempts(), 4);
+    assert!(retry.is_finished());
+    assert_eq!(*outcome.lock().unwrap(), Some(Err("fail")));
+}
+
+#[test]
+fn backoff_retry_cancel() {
+    static ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+
+    let retry = Backoff::new(time::Duration::from_millis(100)).jitter(Jitter::Decorrelated).retry(|| {
+        ATTEMPTS.fetch_add(1, Ordering::AcqRel);
+        Err::<(), ()>(())
+    }, |_| panic!("Should not complete")).expect("To start retry");
+
+    retry.cancel();
+    assert!(retry.is_finished());
+
+    std::thread::sleep(time::Duration::from_millis(400));
+    assert_eq!(ATTEMPTS.load(Ordering::Acquire), 1);
+}
+
+#[test]
+fn backoff_retry_drop_waits_for_attempt() {
+    let done = Arc::new(AtomicBool::new(false));
+    let result = done.clone();
+    let mut attempt = 0;
+
+    let op = move || {
+        attempt += 1;
+        if attempt == 1 {
+            return Err(());
+        }
+
+        std::thread::sleep(time::Duration::from_millis(200));
+        result.store(true, Ordering::Release);
+        Ok(())
+    };
+
+    let retry = Backoff::new(time::Duration::from_millis(10)).jitter(Jitter::None).retry(op, |_| {}).expect("To start retry");
+    assert_eq!(retry.attempts(), 1);
+
+    //Second attempt is running on timer's thread
+    std::thread::sleep(time::Duration::from_millis(100));
+    drop(retry);
+    assert!(done.load(Ordering::Acquire));
+}
+
+#[test]
+fn backoff_retry_self_drop() {
+    static RETRY: Mutex<Option<Retry>> = Mutex::new(None);
+    static DROPPED: AtomicBool = AtomicBool::new(false);
+    static FINISHED: AtomicBool = AtomicBool::new(false);
+
+    struct Guard;
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            DROPPED.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let guard = Guard;
+    let mut attempt = 0;
+    let op = move || {
+        let _guard = &guard;
+        attempt += 1;
+        if attempt == 1 {
+            return Err(());
+        }
+
+        //Releases retry, while attempt is still running
+        drop(RETRY.lock().unwrap().take());
+        assert!(!DROPPED.load(Ordering::SeqCst));
+        FINISHED.store(true, Ordering::SeqCst);
+        Err(())
+    };
+
+    let retry = Backoff::new(time::Duration::from_millis(50)).jitter(Jitter::None).retry(op, |_: Result<(), ()>| {}).expect("To start retry");
+    *RETRY.lock().unwrap() = Some(retry);
+
+    std::thread::sleep(time::Duration::from_millis(300));
+    assert!(FINISHED.load(Ordering::SeqCst));
+    assert!(DROPPED.load(Ordering::SeqCst));
+}
@@ -0,0 +1,47 @@
+#![cfg(not(any(feature = "mock", feature = "custom-backend")))]
+
+use os_timer::TokenBucket;
+
+use core::time;
+use std::sync::Arc;
+use std::time::Instant;
+
+#[test]
+fn bucket_try_acquire_refills() {
+    let bucket = TokenBucket::new(5, 2, time::Duration::from_millis(100)).expect("To create bucket");
+    assert_eq!(bucket.capacity(), 5);
+    assert_eq!(bucket.available(), 5);
+
+    assert!(bucket.try_acquire(3));
+    assert!(!bucket.try_acquire(3));
+    assert!(bucket.try_acquire(2));
+    assert!(!bucket.try_acquire(1));
+    assert_eq!(bucket.available(), 0);
+
+    std::thread::sleep(time::Duration::from_millis(150));
+    assert_eq!(bucket.available(), 2);
+
+    //Never exceeds capacity
+    std::thread::sleep(time::Duration::from_millis(400));
+    assert_eq!(bucket.available(), 5);
+}
+
+#[test]
+fn bucket_acquire_blocks_until_refill() {
+    let bucket = Arc::new(TokenBucket::new(2, 1, time::Duration::from_millis(100)).expect("To create bucket"));
+    assert!(bucket.try_acquire(2));
+
+    let start = Instant::now();
+    let workers = (0..2).map(|_| {
+        let bucket = bucket.clone();
+        std::thread::spawn(move || bucket.acquire(1))
+    }).collect::<Vec<_>>();
+
+    for worker in workers {
+        worker.join().expect("To acquire token");
+    }
+
+    //Two refills are required for both workers to proceed
+    assert!(start.elapsed() >= time::Duration::from_millis(150));
+    assert_eq!(bucket.available(), 0);
+}
@@ -0,0 +1,47 @@
+#![cfg(all(feature = "capi", not(feature = "custom-backend")))]
+
+use os_timer::capi::*;
+
+use core::ptr;
+use core::ffi::c_void;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+unsafe extern "C" fn cb(data: *mut c_void) {
+    let count = &*(data as *const AtomicU8);
+    count.fetch_add(1, Ordering::SeqCst);
+}
+
+#[test]
+fn capi_schedule_and_destroy() {
+    static COUNT: AtomicU8 = AtomicU8::new(0);
+
+    unsafe {
+        assert!(os_timer_new(None, ptr::null_mut()).is_null());
+
+        let timer = os_timer_new(Some(cb), &COUNT as *const AtomicU8 as *mut c_void);
+        assert!(!timer.is_null());
+        assert!(!os_timer_schedule_once(timer, 0));
+
+        let mut remaining = 0;
+        assert!(!os_timer_remaining(timer, &mut remaining));
+        assert!(os_timer_schedule_once(timer, 50_000_000));
+        assert!(os_timer_is_scheduled(timer));
+        assert!(os_timer_remaining(timer, &mut remaining));
+        assert!(remaining > 0 && remaining <= 50_000_000);
+
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        assert_eq!(COUNT.load(Ordering::SeqCst), 1);
+
+        assert!(!os_timer_schedule_interval(timer, 0, 0));
+        assert!(os_timer_schedule_interval(timer, 0, 50_000_000));
+        assert!(os_timer_is_scheduled(timer));
+        assert!(os_timer_remaining(timer, &mut remaining));
+        assert!(remaining > 0 && remaining <= 50_000_000);
+
+        assert!(os_timer_schedule_interval(timer, 50_000_000, 50_000_000));
+        os_timer_cancel(timer);
+        assert!(!os_timer_is_scheduled(timer));
+
+        os_timer_destroy(timer);
+    }
+}
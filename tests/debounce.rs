@@ -0,0 +1,155 @@
+#![cfg(not(any(feature = "mock", feature = "custom-backend")))]
+
+use os_timer::{Debouncer, Throttler};
+
+use core::time;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn debouncer_emits_last_value() {
+    let emitted = Arc::new(Mutex::new(Vec::new()));
+    let result = emitted.clone();
+
+    let debouncer = Debouncer::new(time::Duration::from_millis(200), move |value: u32| {
+        result.lock().unwrap().push(value);
+    }).expect("To create debouncer");
+
+    assert!(!debouncer.is_pending());
+    for value in 1..=3 {
+        debouncer.push(value);
+        std::thread::sleep(time::Duration::from_millis(50));
+    }
+    assert!(debouncer.is_pending());
+    assert!(emitted.lock().unwrap().is_empty());
+
+    std::thread::sleep(time::Duration::from_millis(400));
+    assert!(!debouncer.is_pending());
+    assert_eq!(*emitted.lock().unwrap(), [3]);
+
+    debouncer.push(4);
+    debouncer.cancel();
+    assert!(!debouncer.is_pending());
+
+    std::thread::sleep(time::Duration::from_millis(400));
+    assert_eq!(*emitted.lock().unwrap(), [3]);
+}
+
+#[test]
+fn debouncer_drop_waits_for_callback() {
+    let done = Arc::new(Mutex::new(false));
+    let result = done.clone();
+
+    let debouncer = Debouncer::new(time::Duration::from_millis(10), move |_: u32| {
+        std::thread::sleep(time::Duration::from_millis(200));
+        *result.lock().unwrap() = true;
+    }).expect("To create debouncer");
+
+    debouncer.push(1);
+    std::thread::sleep(time::Duration::from_millis(100));
+    drop(debouncer);
+    assert!(*done.lock().unwrap());
+}
+
+#[test]
+fn throttler_leading_and_trailing() {
+    let emitted = Arc::new(Mutex::new(Vec::new()));
+    let result = emitted.clone();
+
+    let throttler = Throttler::new(time::Duration::from_millis(300), move |value: u32| {
+        result.lock().unwrap().push(value);
+    }).expect("To create throttler");
+
+    assert!(!throttler.is_active());
+    throttler.push(1);
+    assert!(throttler.is_active());
+    throttler.push(2);
+    throttler.push(3);
+    assert_eq!(*emitted.lock().unwrap(), [1]);
+
+    std::thread::sleep(time::Duration::from_millis(450));
+    assert_eq!(*emitted.lock().unwrap(), [1, 3]);
+    //Trailing value opens new window
+    assert!(throttler.is_active());
+
+    std::thread::sleep(time::Duration::from_millis(300));
+    assert!(!throttler.is_active());
+    assert_eq!(*emitted.lock().unwrap(), [1, 3]);
+}
+
+#[test]
+fn throttler_trailing_only() {
+    let emitted = Arc::new(Mutex::new(Vec::new()));
+    let result = emitted.clone();
+
+    let throttler = Throttler::new(time::Duration::from_millis(200), move |value: u32| {
+        result.lock().unwrap().push(value);
+    }).expect("To create throttler").leading(false);
+
+    throttler.push(1);
+    throttler.push(2);
+    assert!(emitted.lock().unwrap().is_empty());
+
+    std::thread::sleep(time::Duration::from_millis(300));
+    assert_eq!(*emitted.lock().unwrap(), [2]);
+}
+
+#[test]
+fn throttler_self_drop() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    static THROTTLER: Mutex<Option<Throttler<u32>>> = Mutex::new(None);
+    static DROPPED: AtomicBool = AtomicBool::new(false);
+    static FINISHED: AtomicBool = AtomicBool::new(false);
+
+    struct Guard;
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            DROPPED.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let guard = Guard;
+    let throttler = Throttler::new(time::Duration::from_millis(100), move |value: u32| {
+        let _guard = &guard;
+        if value == 2 {
+            //Trailing edge releases throttler, while callback is still running
+            drop(THROTTLER.lock().unwrap().take());
+            assert!(!DROPPED.load(Ordering::SeqCst));
+            FINISHED.store(true, Ordering::SeqCst);
+        }
+    }).expect("To create throttler");
+
+    throttler.push(1);
+    throttler.push(2);
+    *THROTTLER.lock().unwrap() = Some(throttler);
+
+    std::thread::sleep(time::Duration::from_millis(300));
+    assert!(FINISHED.load(Ordering::SeqCst));
+    assert!(DROPPED.load(Ordering::SeqCst));
+}
+
+#[test]
+fn throttler_reentrant_push() {
+    use std::sync::OnceLock;
+
+    static THROTTLER: OnceLock<Throttler<u32>> = OnceLock::new();
+    static EMITTED: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+    let throttler = Throttler::new(time::Duration::from_millis(100), |value: u32| {
+        EMITTED.lock().unwrap().push(value);
+        if value == 1 {
+            //Closes window, so that push emits leading edge right away
+            let throttler = THROTTLER.get().unwrap();
+            throttler.cancel();
+            throttler.push(2);
+            assert_eq!(*EMITTED.lock().unwrap(), [1]);
+        }
+    }).expect("To create throttler");
+    let throttler = THROTTLER.get_or_init(move || throttler);
+
+    throttler.push(1);
+    //Value, emitted from within callback, is passed once it returns
+    assert_eq!(*EMITTED.lock().unwrap(), [1, 2]);
+    throttler.cancel();
+}
@@ -0,0 +1,62 @@
+#![cfg(not(any(feature = "mock", feature = "custom-backend")))]
+
+use os_timer::{Callback, Timer, TimerGroup};
+
+use core::time;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, AtomicBool, Ordering};
+
+#[test]
+fn group_pause_resume_cancel() {
+    let count = Arc::new(AtomicU8::new(0));
+    let group = TimerGroup::new();
+
+    let timers = (0..3).map(|_| {
+        let count = count.clone();
+        let timer = Timer::new(Callback::closure(move || {
+            count.fetch_add(1, Ordering::SeqCst);
+        })).expect("To create timer");
+        assert!(timer.schedule_once(time::Duration::from_millis(100)));
+        group.add(&timer);
+        timer
+    }).collect::<Vec<_>>();
+    assert_eq!(group.len(), 3);
+
+    std::thread::sleep(time::Duration::from_millis(50));
+    assert_eq!(group.pause_all(), 3);
+    std::thread::sleep(time::Duration::from_millis(100));
+    assert_eq!(count.load(Ordering::SeqCst), 0);
+
+    assert_eq!(group.resume_all(), 3);
+    std::thread::sleep(time::Duration::from_millis(100));
+    assert_eq!(count.load(Ordering::SeqCst), 3);
+
+    for timer in timers.iter() {
+        assert!(timer.schedule_once(time::Duration::from_millis(50)));
+    }
+    group.cancel_all();
+    std::thread::sleep(time::Duration::from_millis(100));
+    assert_eq!(count.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn group_shutdown_waits_for_callbacks() {
+    static RUNNING: AtomicBool = AtomicBool::new(false);
+
+    let group = TimerGroup::new();
+    let timer = Timer::new(Callback::plain(|| {
+        RUNNING.store(true, Ordering::SeqCst);
+        std::thread::sleep(time::Duration::from_millis(100));
+        RUNNING.store(false, Ordering::SeqCst);
+    })).expect("To create timer");
+    group.add(&timer);
+
+    assert!(timer.schedule_interval(time::Duration::from_millis(10), time::Duration::from_millis(200)));
+    std::thread::sleep(time::Duration::from_millis(50));
+    assert!(RUNNING.load(Ordering::SeqCst));
+
+    group.shutdown();
+    assert!(!RUNNING.load(Ordering::SeqCst));
+    assert!(!timer.is_scheduled());
+    assert!(!timer.schedule_once(time::Duration::from_millis(10)));
+}
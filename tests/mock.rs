@@ -0,0 +1,267 @@
+#![cfg(feature = "mock")]
+
+use os_timer::{mock, Callback, Next, Timer, TimerQueue, TimerWheel};
+
+use core::time;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+fn counter() -> (Arc<AtomicUsize>, Timer) {
+    let count = Arc::new(AtomicUsize::new(0));
+    let timer = {
+        let count = count.clone();
+        Timer::new(Callback::closure(move || {
+            count.fetch_add(1, Ordering::AcqRel);
+        })).expect("To create timer")
+    };
+    (count, timer)
+}
+
+fn ms(ms: u64) -> time::Duration {
+    time::Duration::from_millis(ms)
+}
+
+//Virtual clock is global, so tests must not run concurrently
+static SERIAL: Mutex<()> = Mutex::new(());
+
+#[test]
+fn mock_fires_in_deadline_order() {
+    let _serial = SERIAL.lock().unwrap_or_else(|error| error.into_inner());
+    let fired = Arc::new(Mutex::new(Vec::new()));
+
+    let timers = [(300u64, 0u64), (100, 0), (200, 150)].iter().enumerate().map(|(idx, (timeout, interval))| {
+        let fired = fired.clone();
+        let timer = Timer::new(Callback::closure(move || fired.lock().unwrap().push(idx))).expect("To create timer");
+        assert!(timer.schedule_interval(time::Duration::from_millis(*timeout), time::Duration::from_millis(*interval)));
+        timer
+    }).collect::<Vec<_>>();
+
+    mock::advance(time::Duration::from_millis(99));
+    assert!(fired.lock().unwrap().is_empty());
+
+    mock::advance(time::Duration::from_millis(1));
+    assert_eq!(*fired.lock().unwrap(), [1]);
+
+    //Periodic timer fires at 200 and 350
+    mock::advance(time::Duration::from_millis(300));
+    assert_eq!(*fired.lock().unwrap(), [1, 2, 0, 2]);
+    assert!(!timers[0].is_scheduled());
+    assert!(timers[2].is_scheduled());
+
+    timers[2].cancel();
+    mock::advance(time::Duration::from_secs(10));
+    assert_eq!(fired.lock().unwrap().len(), 4);
+}
+
+#[test]
+fn mock_drives_utilities() {
+    let _serial = SERIAL.lock().unwrap_or_else(|error| error.into_inner());
+    let fired = Arc::new(Mutex::new(Vec::new()));
+    let queue = TimerQueue::new().expect("To create queue");
+
+    for timeout in [50u64, 20, 30].iter() {
+        let fired = fired.clone();
+        let start = mock::now();
+        queue.insert(time::Duration::from_millis(*timeout), move || fired.lock().unwrap().push(mock::now() - start));
+    }
+
+    mock::advance(time::Duration::from_millis(100));
+    assert_eq!(*fired.lock().unwrap(), [20, 30, 50].iter().map(|ms| time::Duration::from_millis(*ms)).collect::<Vec<_>>());
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn mock_wheel_counts_partial_tick() {
+    let _serial = SERIAL.lock().unwrap_or_else(|error| error.into_inner());
+    let fired = Arc::new(Mutex::new(false));
+
+    let wheel = TimerWheel::new(time::Duration::from_millis(10)).expect("To create wheel");
+    mock::advance(time::Duration::from_millis(5));
+
+    let result = fired.clone();
+    wheel.insert(time::Duration::from_millis(10), move || *result.lock().unwrap() = true);
+
+    //Only 5ms passed since insertion at the next tick
+    mock::advance(time::Duration::from_millis(5));
+    assert!(!*fired.lock().unwrap());
+
+    mock::advance(time::Duration::from_millis(10));
+    assert!(*fired.lock().unwrap());
+    assert!(wheel.is_empty());
+}
+
+#[test]
+fn mock_pause_resume() {
+    let _serial = SERIAL.lock().unwrap_or_else(|error| error.into_inner());
+    let (count, timer) = counter();
+    assert!(!timer.pause());
+    assert!(!timer.resume());
+
+    assert!(timer.schedule_once(ms(400)));
+    mock::advance(ms(200));
+    assert!(timer.pause());
+    assert!(timer.is_paused());
+    assert!(!timer.is_scheduled());
+
+    mock::advance(ms(500));
+    assert_eq!(count.load(Ordering::Acquire), 0);
+
+    assert!(timer.resume());
+    assert!(!timer.is_paused());
+    assert!(timer.is_scheduled());
+    assert_eq!(timer.remaining(), Some(ms(200)));
+
+    mock::advance(ms(199));
+    assert_eq!(count.load(Ordering::Acquire), 0);
+    mock::advance(ms(1));
+    assert_eq!(count.load(Ordering::Acquire), 1);
+
+    //Cancel discards paused timer
+    assert!(timer.schedule_once(ms(200)));
+    assert!(timer.pause());
+    timer.cancel();
+    assert!(!timer.is_paused());
+    assert!(!timer.resume());
+}
+
+#[test]
+fn mock_schedule_times() {
+    let _serial = SERIAL.lock().unwrap_or_else(|error| error.into_inner());
+    let (count, timer) = counter();
+    assert!(timer.schedule().interval(ms(100)).times(3).schedule());
+    assert!(timer.is_scheduled());
+
+    mock::advance(ms(250));
+    assert_eq!(count.load(Ordering::Acquire), 2);
+    assert!(timer.is_scheduled());
+
+    mock::advance(ms(300));
+    assert_eq!(count.load(Ordering::Acquire), 3);
+    assert!(!timer.is_scheduled());
+
+    //Plain schedule is not limited
+    assert!(timer.schedule_interval(ms(100), ms(100)));
+    mock::advance(ms(450));
+    assert_eq!(count.load(Ordering::Acquire), 7);
+    assert!(timer.is_scheduled());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn mock_schedule_until() {
+    let _serial = SERIAL.lock().unwrap_or_else(|error| error.into_inner());
+    let (count, timer) = counter();
+    let deadline = std::time::Instant::now() + ms(350);
+    assert!(timer.schedule().interval(ms(100)).until(deadline).schedule());
+
+    mock::advance(ms(600));
+    assert_eq!(count.load(Ordering::Acquire), 3);
+    assert!(!timer.is_scheduled());
+}
+
+#[test]
+fn mock_schedule_fixed_delay() {
+    let _serial = SERIAL.lock().unwrap_or_else(|error| error.into_inner());
+    let runs = Arc::new(Mutex::new(Vec::new()));
+
+    let timer = {
+        let runs = runs.clone();
+        Timer::new(Callback::closure(move || {
+            //Callback takes 150ms of virtual time
+            let start = mock::now();
+            mock::advance(ms(150));
+            runs.lock().unwrap().push((start, mock::now()));
+        })).expect("To create timer")
+    };
+    let start = mock::now();
+    assert!(timer.schedule().interval(ms(100)).fixed_delay().times(3).schedule());
+
+    mock::advance(ms(1000));
+    let runs = runs.lock().unwrap();
+    let runs = runs.iter().map(|(begin, end)| (*begin - start, *end - start)).collect::<Vec<_>>();
+    assert_eq!(runs, [(ms(100), ms(250)), (ms(350), ms(500)), (ms(600), ms(750))]);
+    assert!(!timer.is_scheduled());
+}
+
+#[test]
+fn mock_schedule_jitter() {
+    let _serial = SERIAL.lock().unwrap_or_else(|error| error.into_inner());
+    let fires = Arc::new(Mutex::new(Vec::new()));
+
+    let timer = {
+        let fires = fires.clone();
+        Timer::new(Callback::closure(move || fires.lock().unwrap().push(mock::now()))).expect("To create timer")
+    };
+    let start = mock::now();
+    assert!(timer.schedule().interval(ms(100)).jitter(ms(50)).times(5).schedule());
+
+    mock::advance(ms(900));
+    let fires = fires.lock().unwrap();
+    assert_eq!(fires.len(), 5);
+    for (idx, fire) in fires.iter().enumerate() {
+        //Jitter is applied to nominal deadline, so offsets do not accumulate
+        let nominal = ms(100 * (idx as u64 + 1));
+        let elapsed = *fire - start;
+        assert!(elapsed >= nominal);
+        assert!(elapsed <= nominal + ms(50));
+    }
+    assert!(!timer.is_scheduled());
+}
+
+#[test]
+fn mock_controlled_callback() {
+    let _serial = SERIAL.lock().unwrap_or_else(|error| error.into_inner());
+    let count = Arc::new(AtomicUsize::new(0));
+
+    let timer = {
+        let count = count.clone();
+        Timer::new(Callback::controlled(move || {
+            match count.fetch_add(1, Ordering::SeqCst) {
+                0 | 1 => Next::RescheduleIn(ms(50)),
+                2 => Next::Continue,
+                _ => Next::Stop,
+            }
+        })).expect("To create timer")
+    };
+
+    //Adaptive one shot
+    assert!(timer.schedule_once(ms(50)));
+    mock::advance(ms(149));
+    assert_eq!(count.load(Ordering::SeqCst), 2);
+    mock::advance(ms(1));
+    assert_eq!(count.load(Ordering::SeqCst), 3);
+    assert!(!timer.is_scheduled());
+
+    //Periodic timer stopped by callback
+    assert!(timer.schedule_interval(ms(50), ms(50)));
+    mock::advance(ms(250));
+    assert_eq!(count.load(Ordering::SeqCst), 4);
+    assert!(!timer.is_scheduled());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn mock_drop_waits_for_callback_in_advance() {
+    use std::sync::mpsc;
+
+    let _serial = SERIAL.lock().unwrap_or_else(|error| error.into_inner());
+    let finished = Arc::new(AtomicUsize::new(0));
+    let (started_tx, started_rx) = mpsc::channel();
+
+    let timer = {
+        let finished = finished.clone();
+        Timer::new(Callback::closure(move || {
+            started_tx.send(()).unwrap();
+            std::thread::sleep(ms(200));
+            finished.fetch_add(1, Ordering::SeqCst);
+        })).expect("To create timer")
+    };
+    assert!(timer.schedule_once(ms(50)));
+
+    let advance = std::thread::spawn(|| mock::advance(ms(50)));
+    started_rx.recv().unwrap();
+    drop(timer);
+    assert_eq!(finished.load(Ordering::SeqCst), 1);
+
+    advance.join().unwrap();
+}
@@ -0,0 +1,69 @@
+#![cfg(not(any(feature = "mock", feature = "custom-backend")))]
+
+use os_timer::TimerQueue;
+
+use core::time;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+#[test]
+fn queue_fires_in_deadline_order() {
+    let fired = Arc::new(Mutex::new(Vec::new()));
+    let queue = TimerQueue::new().expect("To create queue");
+    assert!(queue.is_empty());
+
+    for (idx, timeout) in [300u64, 50, 200, 100].iter().enumerate() {
+        let fired = fired.clone();
+        queue.insert(time::Duration::from_millis(*timeout), move || fired.lock().unwrap().push(idx));
+    }
+    let cancelled = queue.insert(time::Duration::from_millis(10), || panic!("Should be cancelled"));
+    assert_eq!(queue.len(), 5);
+
+    assert!(queue.cancel(cancelled));
+    assert!(!queue.cancel(cancelled));
+    assert_eq!(queue.len(), 4);
+
+    std::thread::sleep(time::Duration::from_millis(250));
+    assert_eq!(*fired.lock().unwrap(), [1, 3, 2]);
+    assert_eq!(queue.len(), 1);
+
+    std::thread::sleep(time::Duration::from_millis(150));
+    assert_eq!(*fired.lock().unwrap(), [1, 3, 2, 0]);
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn queue_exact_deadline() {
+    let fired_at = Arc::new(Mutex::new(None));
+    let queue = TimerQueue::new().expect("To create queue");
+
+    let start = Instant::now();
+    let result = fired_at.clone();
+    queue.insert(time::Duration::from_millis(150), move || *result.lock().unwrap() = Some(Instant::now()));
+    //Later deadline inserted first must not delay earlier one
+    queue.insert(time::Duration::from_secs(10), || ());
+
+    std::thread::sleep(time::Duration::from_millis(300));
+    let elapsed = fired_at.lock().unwrap().expect("To fire").duration_since(start);
+    assert!(elapsed >= time::Duration::from_millis(150));
+    assert!(elapsed < time::Duration::from_millis(250));
+    assert_eq!(queue.len(), 1);
+}
+
+#[test]
+fn queue_job_can_insert() {
+    let fired = Arc::new(Mutex::new(0));
+    let queue = Arc::new(TimerQueue::new().expect("To create queue"));
+
+    let inner_queue = queue.clone();
+    let inner_fired = fired.clone();
+    queue.insert(time::Duration::from_millis(50), move || {
+        *inner_fired.lock().unwrap() += 1;
+        let fired = inner_fired.clone();
+        inner_queue.insert(time::Duration::from_millis(50), move || *fired.lock().unwrap() += 1);
+    });
+
+    std::thread::sleep(time::Duration::from_millis(300));
+    assert_eq!(*fired.lock().unwrap(), 2);
+    assert!(queue.is_empty());
+}
@@ -0,0 +1,32 @@
+#![cfg(not(any(feature = "mock", feature = "custom-backend")))]
+
+use os_timer::scope;
+
+use core::time;
+use core::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Mutex;
+
+#[test]
+fn scope_callbacks_borrow_stack_data() {
+    let count = AtomicU8::new(0);
+    let samples = Mutex::new(Vec::new());
+
+    let result = scope(|s| {
+        let timer = s.timer(|| {
+            let value = count.fetch_add(1, Ordering::SeqCst);
+            samples.lock().unwrap().push(value);
+        }).expect("To create timer");
+
+        assert!(timer.schedule_interval(time::Duration::from_millis(50), time::Duration::from_millis(50)));
+        std::thread::sleep(time::Duration::from_millis(175));
+        timer.is_scheduled()
+    });
+    assert!(result);
+
+    //Timer is stopped once scope returns
+    let fired = count.load(Ordering::SeqCst);
+    assert!(fired >= 3);
+    std::thread::sleep(time::Duration::from_millis(100));
+    assert_eq!(count.load(Ordering::SeqCst), fired);
+    assert_eq!(samples.into_inner().unwrap().len(), fired as usize);
+}
@@ -0,0 +1,23 @@
+#![cfg(not(any(feature = "mock", feature = "custom-backend")))]
+
+use os_timer::spawn_after;
+
+use core::time;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[test]
+fn spawn_after_runs_once_and_releases_job() {
+    let count = Arc::new(AtomicU8::new(0));
+    let job_count = count.clone();
+
+    assert!(spawn_after(time::Duration::from_millis(50), move || {
+        job_count.fetch_add(1, Ordering::SeqCst);
+    }));
+    assert_eq!(Arc::strong_count(&count), 2);
+
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+    //Closure, owning captured state, is dropped with timer
+    assert_eq!(Arc::strong_count(&count), 1);
+}
@@ -1,3 +1,5 @@
+#![cfg(not(any(feature = "mock", feature = "custom-backend")))]
+
 use os_timer::{Callback, Timer};
 
 use core::time;
@@ -95,3 +97,297 @@ fn timer_schedule_interval_without_initial() {
     std::thread::sleep(time::Duration::from_secs(1));
     assert_eq!(COUNT.load(Ordering::Acquire), 6);
 }
+
+#[test]
+fn timer_self_cancel_and_reschedule() {
+    use std::sync::Mutex;
+
+    static COUNT: AtomicU8 = AtomicU8::new(0);
+    static TIMER: Mutex<Option<Timer>> = Mutex::new(None);
+
+    fn cb() {
+        let count = COUNT.fetch_add(1, Ordering::SeqCst) + 1;
+        let timer = TIMER.lock().unwrap();
+        let timer = timer.as_ref().expect("Timer to be set");
+        match count {
+            1 | 2 => assert!(timer.schedule_once(time::Duration::from_millis(50))),
+            _ => timer.cancel(),
+        }
+    }
+
+    let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+    assert!(timer.schedule_interval(time::Duration::from_millis(50), time::Duration::from_millis(50)));
+    *TIMER.lock().unwrap() = Some(timer);
+
+    std::thread::sleep(time::Duration::from_millis(300));
+
+    assert_eq!(COUNT.load(Ordering::SeqCst), 3);
+    assert!(!TIMER.lock().unwrap().as_ref().unwrap().is_scheduled());
+}
+
+#[test]
+fn timer_self_drop() {
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::AtomicBool;
+
+    static TIMER: Mutex<Option<Arc<Timer>>> = Mutex::new(None);
+    static DROPPED: AtomicBool = AtomicBool::new(false);
+    static FINISHED: AtomicBool = AtomicBool::new(false);
+
+    struct Guard;
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            DROPPED.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let guard = Guard;
+    let timer = Timer::new(Callback::closure(move || {
+        let _guard = &guard;
+        let timer = TIMER.lock().unwrap().take();
+        //Releases last reference, while closure is still running
+        drop(timer);
+        assert!(!DROPPED.load(Ordering::SeqCst));
+        FINISHED.store(true, Ordering::SeqCst);
+    })).expect("To create timer");
+
+    let timer = Arc::new(timer);
+    assert!(timer.schedule_interval(time::Duration::from_millis(50), time::Duration::from_millis(50)));
+    *TIMER.lock().unwrap() = Some(timer);
+
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert!(FINISHED.load(Ordering::SeqCst));
+    assert!(DROPPED.load(Ordering::SeqCst));
+}
+
+#[test]
+fn timer_drop_waits_for_callback() {
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+
+    let started = Arc::new(AtomicBool::new(false));
+    let finished = Arc::new(AtomicBool::new(false));
+    let captured = Arc::new(());
+
+    let timer = {
+        let started = started.clone();
+        let finished = finished.clone();
+        let captured = captured.clone();
+        Timer::new(Callback::closure(move || {
+            let _captured = &captured;
+            started.store(true, Ordering::SeqCst);
+            std::thread::sleep(time::Duration::from_millis(200));
+            finished.store(true, Ordering::SeqCst);
+        })).expect("To create timer")
+    };
+
+    assert!(timer.schedule_interval(time::Duration::from_millis(10), time::Duration::from_millis(10)));
+    while !started.load(Ordering::SeqCst) {
+        std::thread::yield_now();
+    }
+
+    timer.cancel();
+    assert!(finished.load(Ordering::SeqCst));
+    assert!(!timer.is_scheduled());
+
+    finished.store(false, Ordering::SeqCst);
+    started.store(false, Ordering::SeqCst);
+    assert!(timer.schedule_once(time::Duration::from_millis(10)));
+    while !started.load(Ordering::SeqCst) {
+        std::thread::yield_now();
+    }
+
+    drop(timer);
+    assert!(finished.load(Ordering::SeqCst));
+    //Closure is released together with timer
+    assert_eq!(Arc::strong_count(&captured), 1);
+}
+
+#[test]
+fn timer_once_callback() {
+    use std::sync::Arc;
+
+    static COUNT: AtomicU8 = AtomicU8::new(0);
+
+    let resource = Arc::new(());
+    let owned = resource.clone();
+    let timer = Timer::new(Callback::once(move || {
+        let _owned = owned;
+        COUNT.fetch_add(1, Ordering::SeqCst);
+    })).expect("To create timer");
+
+    //Later alarms do nothing
+    assert!(timer.schedule_interval(time::Duration::from_millis(50), time::Duration::from_millis(50)));
+    std::thread::sleep(time::Duration::from_millis(250));
+    timer.cancel();
+
+    assert_eq!(COUNT.load(Ordering::SeqCst), 1);
+    //Closure is consumed with its captures on first alarm
+    assert_eq!(Arc::strong_count(&resource), 1);
+}
+
+#[test]
+fn timer_with_data_callback() {
+    use std::sync::Arc;
+
+    fn cb(count: &AtomicU8) {
+        count.fetch_add(1, Ordering::SeqCst);
+    }
+
+    let count = Arc::new(AtomicU8::new(0));
+    let timer = Timer::new(Callback::with_data(count.clone(), cb)).expect("To create timer");
+    assert_eq!(Arc::strong_count(&count), 2);
+
+    assert!(timer.schedule_once(time::Duration::from_millis(50)));
+    std::thread::sleep(time::Duration::from_millis(150));
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+
+    drop(timer);
+    assert_eq!(Arc::strong_count(&count), 1);
+}
+
+#[test]
+fn timer_set_callback() {
+    use std::sync::Arc;
+
+    static FIRST: AtomicU8 = AtomicU8::new(0);
+    static SECOND: AtomicU8 = AtomicU8::new(0);
+
+    let resource = Arc::new(());
+    let owned = resource.clone();
+    let timer = Timer::new(Callback::closure(move || {
+        let _owned = &owned;
+        FIRST.fetch_add(1, Ordering::SeqCst);
+    })).expect("To create timer");
+
+    assert!(timer.schedule_interval(time::Duration::from_millis(100), time::Duration::from_millis(100)));
+    std::thread::sleep(time::Duration::from_millis(150));
+    assert_eq!(FIRST.load(Ordering::SeqCst), 1);
+
+    timer.set_callback(Callback::plain(|| {
+        SECOND.fetch_add(1, Ordering::SeqCst);
+    }));
+    //Old callback is released, while schedule is kept
+    assert_eq!(Arc::strong_count(&resource), 1);
+    assert!(timer.is_scheduled());
+
+    std::thread::sleep(time::Duration::from_millis(100));
+    assert_eq!(FIRST.load(Ordering::SeqCst), 1);
+    assert_eq!(SECOND.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn timer_weak_callback() {
+    use std::sync::Arc;
+
+    fn cb(count: &AtomicU8) {
+        count.fetch_add(1, Ordering::SeqCst);
+    }
+
+    let target = Arc::new(AtomicU8::new(0));
+    let timer = Timer::new(Callback::weak(Arc::downgrade(&target), cb)).expect("To create timer");
+
+    assert!(timer.schedule_interval(time::Duration::from_millis(50), time::Duration::from_millis(50)));
+    std::thread::sleep(time::Duration::from_millis(125));
+    assert_eq!(target.load(Ordering::SeqCst), 2);
+    assert!(timer.is_scheduled());
+
+    //Timer doesn't keep target alive and stops once it is gone
+    drop(target);
+    std::thread::sleep(time::Duration::from_millis(100));
+    assert!(!timer.is_scheduled());
+}
+
+#[test]
+fn timer_handle() {
+    use os_timer::Gone;
+
+    static COUNT: AtomicU8 = AtomicU8::new(0);
+
+    fn cb() {
+        COUNT.fetch_add(1, Ordering::SeqCst);
+    }
+
+    let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+    let handle = timer.handle();
+    assert_eq!(handle.remaining(), Ok(None));
+
+    let worker = handle.clone();
+    std::thread::spawn(move || {
+        assert!(worker.schedule().unwrap().initial(time::Duration::from_millis(100)).schedule());
+    }).join().expect("To schedule from other thread");
+
+    assert_eq!(handle.is_scheduled(), Ok(true));
+    let remaining = handle.remaining().unwrap().expect("To have remaining time");
+    assert!(remaining <= time::Duration::from_millis(100));
+    handle.cancel().unwrap();
+    assert!(!timer.is_scheduled());
+
+    assert!(timer.schedule_once(time::Duration::from_millis(50)));
+    std::thread::sleep(time::Duration::from_millis(100));
+    assert_eq!(COUNT.load(Ordering::SeqCst), 1);
+
+    drop(timer);
+    assert_eq!(handle.is_scheduled(), Err(Gone));
+    assert_eq!(handle.cancel(), Err(Gone));
+    assert!(handle.schedule().is_err());
+    assert_eq!(handle.remaining(), Err(Gone));
+}
+
+#[test]
+fn timer_schedule_guarded() {
+    static COUNT: AtomicU8 = AtomicU8::new(0);
+
+    fn cb() {
+        COUNT.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn handle_request(timer: &Timer, fail: bool) -> Result<(), ()> {
+        let guard = timer.schedule().initial(time::Duration::from_millis(50)).schedule_guarded().expect("To schedule");
+        assert!(guard.is_scheduled());
+        if fail {
+            return Err(());
+        }
+        guard.dismiss();
+        Ok(())
+    }
+
+    let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+
+    //Early return cancels timeout
+    assert!(handle_request(&timer, true).is_err());
+    assert!(!timer.is_scheduled());
+
+    assert!(handle_request(&timer, false).is_ok());
+    assert!(timer.is_scheduled());
+    std::thread::sleep(time::Duration::from_millis(100));
+    assert_eq!(COUNT.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn timer_raw_parts_round_trip() {
+    static COUNT: AtomicU8 = AtomicU8::new(0);
+
+    fn cb() {
+        COUNT.fetch_add(1, Ordering::SeqCst);
+    }
+
+    let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+    let raw = timer.as_raw();
+    assert!(!raw.is_null());
+    assert!(timer.schedule_once(time::Duration::from_millis(50)));
+
+    let (handle, context) = timer.into_raw_parts();
+    assert_eq!(handle, raw);
+    std::thread::sleep(time::Duration::from_millis(100));
+    assert_eq!(COUNT.load(Ordering::SeqCst), 1);
+
+    let timer = unsafe {
+        Timer::from_raw_parts(handle, context)
+    };
+    assert_eq!(timer.as_raw(), raw);
+    assert!(timer.schedule_once(time::Duration::from_millis(50)));
+    std::thread::sleep(time::Duration::from_millis(100));
+    assert_eq!(COUNT.load(Ordering::SeqCst), 2);
+}
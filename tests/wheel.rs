@@ -0,0 +1,93 @@
+#![cfg(not(any(feature = "mock", feature = "custom-backend")))]
+
+use os_timer::TimerWheel;
+
+use core::time;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[test]
+fn wheel_fires_in_order() {
+    let fired = Arc::new(Mutex::new(Vec::new()));
+    let wheel = TimerWheel::new(time::Duration::from_millis(10)).expect("To create wheel");
+    assert!(wheel.is_empty());
+
+    //Last one requires cascading from upper level
+    for (idx, timeout) in [300u64, 50, 1000, 150].iter().enumerate() {
+        let fired = fired.clone();
+        wheel.insert(time::Duration::from_millis(*timeout), move || fired.lock().unwrap().push(idx));
+    }
+    let cancelled = wheel.insert(time::Duration::from_millis(100), || panic!("Should be cancelled"));
+    assert_eq!(wheel.len(), 5);
+
+    assert!(wheel.cancel(cancelled));
+    assert!(!wheel.cancel(cancelled));
+    assert_eq!(wheel.len(), 4);
+
+    std::thread::sleep(time::Duration::from_millis(500));
+    assert_eq!(*fired.lock().unwrap(), [1, 3, 0]);
+    assert_eq!(wheel.len(), 1);
+
+    std::thread::sleep(time::Duration::from_millis(700));
+    assert_eq!(*fired.lock().unwrap(), [1, 3, 0, 2]);
+    assert!(wheel.is_empty());
+}
+
+#[test]
+fn wheel_many_timeouts() {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    const TOTAL: usize = 100_000;
+
+    let wheel = TimerWheel::new(time::Duration::from_millis(5)).expect("To create wheel");
+
+    let mut ids = Vec::with_capacity(TOTAL);
+    for idx in 0..TOTAL {
+        let timeout = time::Duration::from_millis(500 + (idx % 400) as u64);
+        ids.push(wheel.insert(timeout, || {
+            COUNT.fetch_add(1, Ordering::AcqRel);
+        }));
+    }
+
+    for id in ids.iter().step_by(2) {
+        assert!(wheel.cancel(*id));
+    }
+    assert_eq!(wheel.len(), TOTAL / 2);
+
+    std::thread::sleep(time::Duration::from_millis(1500));
+    assert_eq!(COUNT.load(Ordering::Acquire), TOTAL / 2);
+    assert!(wheel.is_empty());
+
+    //Stale handles must not cancel re-used entries
+    let id = wheel.insert(time::Duration::from_millis(50), || {
+        COUNT.fetch_add(1, Ordering::AcqRel);
+    });
+    assert!(!wheel.cancel(ids[0]));
+    assert_eq!(wheel.len(), 1);
+    assert!(wheel.cancel(id));
+}
+
+#[test]
+fn wheel_never_fires_early() {
+    let wheel = TimerWheel::new(time::Duration::from_millis(20)).expect("To create wheel");
+    let early = Arc::new(AtomicUsize::new(0));
+    let count = Arc::new(AtomicUsize::new(0));
+
+    //Insert at different points within tick
+    for idx in 0..10u64 {
+        let timeout = time::Duration::from_millis(30 + idx * 7);
+        let inserted = std::time::Instant::now();
+        let early = early.clone();
+        let count = count.clone();
+        wheel.insert(timeout, move || {
+            if inserted.elapsed() < timeout {
+                early.fetch_add(1, Ordering::AcqRel);
+            }
+            count.fetch_add(1, Ordering::AcqRel);
+        });
+        std::thread::sleep(time::Duration::from_millis(7));
+    }
+
+    std::thread::sleep(time::Duration::from_millis(300));
+    assert_eq!(count.load(Ordering::Acquire), 10);
+    assert_eq!(early.load(Ordering::Acquire), 0);
+}